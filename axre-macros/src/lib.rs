@@ -0,0 +1,298 @@
+//! Attribute macros of axre, re-exported by `axre` with `macros` feature.
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, AttributeArgs, FnArg, ItemFn, Lit, Meta, NestedMeta, ReturnType};
+
+/// Set options of `JsonConfig` for the extractors of a handler.
+///
+/// Each option calls the builder of `JsonConfig` by its name. A flag passes `true`, e.g.
+/// `json_errors`, or the given bool, e.g. `etag = false`. Options of a number of bytes,
+/// e.g. `limit` and `wire_limit`, take a number or a size like `"512kb"` or `"1mb"`.
+/// Other options take a literal of their type as is, e.g. `preview = 64` or
+/// `json_part = "meta"`.
+///
+/// Only the options of `JsonConfig` taking a single literal are supported, any other
+/// name, e.g. a hook like `post_parse`, fails to compile with the list of options.
+///
+/// ```rust,ignore
+/// #[axre::json(limit = "1mb", reject_duplicate_keys)]
+/// async fn upload(user: Json<User>) -> String {
+///     format!("Welcome {}!", user.name)
+/// }
+///
+/// // instead of web::resource("/upload").app_state(config.limit(1_048_576)...)
+/// web::resource("/upload").route(web::post().to(upload))
+/// ```
+///
+/// The config starts from the config resolved for the request as usual, e.g. registered
+/// with `app_state` or the default with the default limit, and only the options of the
+/// attribute are changed, so other settings like hooks are kept. The handler is
+/// rewritten to store the config in request extensions before it runs the extractors of
+/// its arguments, errors of extractors are rendered by `DefaultError`. See
+/// `examples/json_attribute.rs`.
+#[proc_macro_attribute]
+pub fn json(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let item = parse_macro_input!(item as ItemFn);
+    match expand(args, item) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Type of value taken by an option of the attribute.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    /// `bool`, `true` when only the name is given
+    Flag,
+    /// Number of bytes, either an integer or a size like `"1mb"`
+    Size,
+    /// `usize` of other units
+    Number,
+    /// `&str`
+    Str,
+}
+
+/// Options of the attribute, the builders of `JsonConfig` taking a single literal.
+const OPTIONS: &[(&str, Kind)] = &[
+    ("limit", Kind::Size),
+    ("wire_limit", Kind::Size),
+    ("alloc_budget", Kind::Size),
+    ("coalesce", Kind::Size),
+    ("compress_above", Kind::Size),
+    ("decode_at_once_below", Kind::Size),
+    ("limit_kb", Kind::Number),
+    ("limit_mb", Kind::Number),
+    ("preview", Kind::Number),
+    ("capture_rejected", Kind::Number),
+    ("json_part", Kind::Str),
+    ("limit_from_schema", Kind::Flag),
+    ("coerce_numeric_strings", Kind::Flag),
+    ("reject_duplicate_keys", Kind::Flag),
+    ("reject_trailing_bytes", Kind::Flag),
+    ("json_errors", Kind::Flag),
+    ("etag", Kind::Flag),
+    ("response_charset", Kind::Flag),
+    ("strict_accept", Kind::Flag),
+    ("base64_body", Kind::Flag),
+    ("skip_content_type_check", Kind::Flag),
+    ("allow_empty_body", Kind::Flag),
+    ("accept_application_wildcard", Kind::Flag),
+];
+
+/// Builder call of an option, checking the value by the kind of option.
+fn builder(name: &syn::Path, lit: Option<&Lit>) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = name
+        .get_ident()
+        .ok_or_else(|| syn::Error::new_spanned(name, "expected option name"))?;
+    let kind = OPTIONS
+        .iter()
+        .find(|(option, _)| ident == option)
+        .map(|(_, kind)| *kind)
+        .ok_or_else(|| {
+            let options: Vec<_> = OPTIONS.iter().map(|(option, _)| *option).collect();
+            syn::Error::new_spanned(
+                ident,
+                format!(
+                    "unknown option `{}`, expected one of {}",
+                    ident,
+                    options.join(", ")
+                ),
+            )
+        })?;
+
+    let value = match (kind, lit) {
+        (Kind::Flag, None) => quote!(true),
+        (Kind::Flag, Some(lit @ Lit::Bool(_))) => quote!(#lit),
+        (Kind::Size, Some(Lit::Str(size))) => {
+            let size = parse_size(&size.value())
+                .ok_or_else(|| syn::Error::new_spanned(size, "invalid size, e.g. \"512kb\""))?;
+            quote!(#size)
+        }
+        (Kind::Size | Kind::Number, Some(lit @ Lit::Int(_))) => quote!(#lit),
+        (Kind::Str, Some(lit @ Lit::Str(_))) => quote!(#lit),
+        (kind, _) => {
+            let expected = match kind {
+                Kind::Flag => "a flag or `= true`/`= false`",
+                Kind::Size => "`= <bytes>` or a size like `= \"1mb\"`",
+                Kind::Number => "`= <number>`",
+                Kind::Str => "`= \"<string>\"`",
+            };
+            return Err(syn::Error::new_spanned(
+                name,
+                format!("option `{}` expects {}", ident, expected),
+            ));
+        }
+    };
+    Ok(quote!(.#ident(#value)))
+}
+
+fn expand(args: AttributeArgs, item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let mut builders = Vec::new();
+    for arg in args {
+        let builder = match arg {
+            NestedMeta::Meta(Meta::Path(ref path)) => builder(path, None)?,
+            NestedMeta::Meta(Meta::NameValue(ref nv)) => builder(&nv.path, Some(&nv.lit))?,
+            arg => {
+                return Err(syn::Error::new_spanned(
+                    arg,
+                    "expected `name` or `name = value`",
+                ))
+            }
+        };
+        builders.push(builder);
+    }
+
+    if item.sig.asyncness.is_none() {
+        return Err(syn::Error::new_spanned(
+            item.sig.fn_token,
+            "handler must be async",
+        ));
+    }
+    let mut bindings = Vec::new();
+    for input in &item.sig.inputs {
+        match input {
+            FnArg::Typed(arg) => bindings.push((&arg.pat, &arg.ty)),
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "handler can not take self",
+                ))
+            }
+        }
+    }
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = &item;
+    let name = &sig.ident;
+    let output = match &sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    let req = syn::Ident::new("__axre_req", Span::mixed_site());
+    let payload = syn::Ident::new("__axre_payload", Span::mixed_site());
+    let config = syn::Ident::new("__axre_config", Span::mixed_site());
+    let extract = bindings.iter().map(|(pat, ty)| {
+        quote! {
+            let #pat: #ty = match <#ty as ::axre::__private::FromRequest<
+                ::axre::__private::DefaultError,
+            >>::from_request(&#req, &mut #payload)
+            .await
+            {
+                Ok(value) => value,
+                Err(e) => {
+                    let e: <::axre::__private::DefaultError as ::axre::__private::ErrorRenderer>::Container = e.into();
+                    return ::axre::__private::Either::Left(
+                        ::axre::__private::ErrorContainer::error_response(&e, &#req),
+                    );
+                }
+            };
+        }
+    });
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis async fn #name(
+            #req: ::axre::__private::HttpRequest,
+            #payload: ::axre::__private::PayloadExtractor,
+        ) -> ::axre::__private::Either<::axre::__private::HttpResponse, #output> {
+            let #config = ::axre::__private::json_config(&#req) #(#builders)*;
+            #req.extensions_mut().insert(#config);
+            let mut #payload = #payload.into_inner();
+            #(#extract)*
+            ::axre::__private::Either::Right(async move #block.await)
+        }
+    })
+}
+
+/// Parse size like `1048576`, `512kb` or `1mb`, units are case-insensitive.
+fn parse_size(size: &str) -> Option<usize> {
+    let size = size.trim().to_ascii_lowercase();
+    let (number, unit) = match size.find(|c: char| !c.is_ascii_digit()) {
+        Some(at) => size.split_at(at),
+        None => (size.as_str(), ""),
+    };
+    let unit = match unit.trim() {
+        "" | "b" => 1,
+        "kb" => 1024,
+        "mb" => 1024 * 1024,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<usize>().ok()?.checked_mul(unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expand the attribute with the arguments on a trivial handler.
+    fn expand_args(args: &str) -> syn::Result<String> {
+        let args = match syn::parse_str::<Meta>(&format!("json({})", args))? {
+            Meta::List(list) => list.nested.into_iter().collect(),
+            _ => unreachable!(),
+        };
+        let item: ItemFn = syn::parse_str("async fn index() -> String { String::new() }")?;
+        expand(args, item).map(|tokens| tokens.to_string())
+    }
+
+    fn error(args: &str) -> String {
+        expand_args(args).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_options() {
+        let expanded =
+            expand_args(r#"limit = "1kb", json_part = "meta", etag = false, json_errors"#).unwrap();
+        assert!(expanded.contains(". limit (1024usize)"), "{}", expanded);
+        assert!(expanded.contains(r#". json_part ("meta")"#), "{}", expanded);
+        assert!(expanded.contains(". etag (false)"), "{}", expanded);
+        assert!(expanded.contains(". json_errors (true)"), "{}", expanded);
+
+        let expanded = expand_args("wire_limit = 2048, preview = 64").unwrap();
+        assert!(expanded.contains(". wire_limit (2048)"), "{}", expanded);
+        assert!(expanded.contains(". preview (64)"), "{}", expanded);
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        assert!(error("deny_unknown")
+            .starts_with("unknown option `deny_unknown`, expected one of limit,"));
+        assert_eq!(
+            error(r#"limit = "1 parsec""#),
+            r#"invalid size, e.g. "512kb""#
+        );
+        assert_eq!(
+            error(r#"preview = "64""#),
+            "option `preview` expects `= <number>`"
+        );
+        assert_eq!(
+            error("json_part = 1"),
+            r#"option `json_part` expects `= "<string>"`"#
+        );
+        assert_eq!(
+            error("limit"),
+            r#"option `limit` expects `= <bytes>` or a size like `= "1mb"`"#
+        );
+        assert_eq!(
+            error(r#"etag = "yes""#),
+            "option `etag` expects a flag or `= true`/`= false`"
+        );
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1048576"), Some(1_048_576));
+        assert_eq!(parse_size("512kb"), Some(512 * 1024));
+        assert_eq!(parse_size(" 1MB "), Some(1024 * 1024));
+        assert_eq!(parse_size("8 b"), Some(8));
+        assert_eq!(parse_size("1.5mb"), None);
+        assert_eq!(parse_size("mb"), None);
+        assert_eq!(parse_size("99999999999999999999gb"), None);
+    }
+}
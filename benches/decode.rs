@@ -0,0 +1,70 @@
+//! Compare decoding small gzip bodies at once with decoding them as a stream.
+mod common;
+
+use axre::types::json::JsonConfig;
+use axre::types::LimitedBytes;
+use common::block_on;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use ntex::http::{h1, Payload};
+use ntex::util::Bytes;
+use ntex::web::test::TestRequest;
+use ntex::web::{DefaultError, FromRequest};
+use std::io::Write;
+
+fn read(config: JsonConfig, body: &Bytes) -> usize {
+    let (req, _) = TestRequest::default()
+        .header("content-encoding", "gzip")
+        .header("content-length", body.len().to_string())
+        .state(config)
+        .to_http_parts();
+    let (mut sender, payload) = h1::Payload::create(false);
+    // a small body usually arrives in a few chunks
+    for chunk in body.chunks(512) {
+        sender.feed_data(Bytes::copy_from_slice(chunk));
+    }
+    sender.feed_eof();
+    let mut payload = Payload::from(payload);
+
+    let fut = <LimitedBytes as FromRequest<DefaultError>>::from_request(&req, &mut payload);
+    block_on(fut).unwrap().len()
+}
+
+fn gzip(size: usize) -> Bytes {
+    let json = br#"{"name":"axre","tags":["a","b","c"]},"#;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for _ in 0..size / json.len() {
+        encoder.write_all(json).unwrap();
+    }
+    Bytes::from(encoder.finish().unwrap())
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for size in [1_024, 16_384, 65_536] {
+        let body = gzip(size);
+        group.bench_with_input(BenchmarkId::new("stream", size), &body, |b, body| {
+            b.iter(|| {
+                read(
+                    JsonConfig::default().limit(1 << 20).decode_at_once_below(0),
+                    body,
+                )
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("at_once", size), &body, |b, body| {
+            b.iter(|| {
+                read(
+                    JsonConfig::default()
+                        .limit(1 << 20)
+                        .decode_at_once_below(usize::MAX),
+                    body,
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+//! Compare reading a body received in many tiny chunks, with and without coalescing,
+//! and reading a body in chunks of uneven sizes, which would make a naive buffer
+//! reallocate for most chunks.
+mod common;
+
+use axre::types::json::JsonConfig;
+use axre::types::LimitedBytes;
+use common::block_on;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ntex::http::{h1, Payload};
+use ntex::util::Bytes;
+use ntex::web::test::TestRequest;
+use ntex::web::{DefaultError, FromRequest};
+
+const CHUNK: &[u8] = b"[1,2,3,4,5,6,7,8],";
+
+fn read(config: JsonConfig, chunks: &[Bytes]) -> usize {
+    let (req, _) = TestRequest::default().state(config).to_http_parts();
+    let (mut sender, payload) = h1::Payload::create(false);
+    for chunk in chunks {
+        sender.feed_data(chunk.clone());
+    }
+    sender.feed_eof();
+    let mut payload = Payload::from(payload);
+
+    let fut = <LimitedBytes as FromRequest<DefaultError>>::from_request(&req, &mut payload);
+    block_on(fut).unwrap().len()
+}
+
+fn coalesce(c: &mut Criterion) {
+    let mut group = c.benchmark_group("coalesce");
+    for count in [1_000, 10_000] {
+        let chunks = vec![Bytes::from_static(CHUNK); count];
+        group.bench_with_input(BenchmarkId::new("off", count), &chunks, |b, chunks| {
+            b.iter(|| read(JsonConfig::default().limit(1 << 20), chunks))
+        });
+        group.bench_with_input(BenchmarkId::new("16kb", count), &chunks, |b, chunks| {
+            b.iter(|| {
+                read(
+                    JsonConfig::default().limit(1 << 20).coalesce(16_384),
+                    chunks,
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+fn growth(c: &mut Criterion) {
+    let mut group = c.benchmark_group("growth");
+    // a tiny chunk followed by a chunk filling the rest of a buffer of doubled size
+    let mut chunks = Vec::new();
+    let mut size = 8192;
+    while size <= 1 << 20 {
+        chunks.push(Bytes::from(vec![b' '; 1]));
+        chunks.push(Bytes::from(vec![b' '; size - 1]));
+        size *= 2;
+    }
+    let total: usize = chunks.iter().map(|chunk| chunk.len()).sum();
+    group.bench_function("uneven", |b| {
+        b.iter(|| read(JsonConfig::default().limit(total), &chunks))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, coalesce, growth);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+use axre::types::json::JsonConfig;
+use axre::types::Json;
+use dade_derive::model;
+use ntex::web;
+
+#[model]
+struct User {
+    #[field(min_length = 1, max_length = 10)]
+    name: String,
+}
+
+/// Options of the route are set by the attribute, the other options are kept from
+/// the config of the app, e.g. `json_errors`.
+#[axre::json(limit = "1mb", reject_duplicate_keys)]
+async fn upload(payload: Json<User>) -> String {
+    format!("Welcome {}!", payload.name)
+}
+
+/// Same as `upload`, configured by `app_state` of the resource instead.
+async fn upload_with_state(payload: Json<User>) -> String {
+    format!("Welcome {}!", payload.name)
+}
+
+fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/upload").route(web::post().to(upload)));
+    cfg.service(
+        web::resource("/upload-with-state")
+            .app_state(
+                JsonConfig::default()
+                    .json_errors(true)
+                    .limit(1_048_576)
+                    .reject_duplicate_keys(true),
+            )
+            .route(web::post().to(upload_with_state)),
+    );
+}
+
+#[ntex::main]
+async fn main() -> std::io::Result<()> {
+    web::server(|| {
+        web::App::new()
+            .app_state(JsonConfig::default().json_errors(true))
+            .configure(services)
+    })
+    .bind("127.0.0.1:8080")?
+    .run()
+    .await
+}
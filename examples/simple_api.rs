@@ -12,14 +12,33 @@ async fn index(payload: axre::types::Json<User>) -> String {
     format!("Welcome {}!", payload.name)
 }
 
+/// Reject the body with the same response as the extractor does.
+async fn register(
+    payload: axre::types::Json<User>,
+) -> Result<String, axre::errors::JsonPayloadError> {
+    if payload.name == "admin" {
+        return Err(axre::errors::JsonPayloadError::Rejected {
+            status: ntex::http::StatusCode::CONFLICT,
+            reason: "name is taken".to_string(),
+        });
+    }
+    Ok(format!("Registered {}!", payload.name))
+}
+
 fn services(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::resource("/welcome").route(web::Route::new().method(ntex::http::Method::POST).to(index)),
     );
+    cfg.service(
+        web::resource("/register")
+            .route(web::Route::new().method(ntex::http::Method::POST).to(register)),
+    );
 }
 
 #[ntex::main]
 async fn main() -> std::io::Result<()> {
+    axre::set_default_json_limit(4096);
+
     web::server(|| web::App::new().configure(services))
         .bind("127.0.0.1:8080")?
         .run()
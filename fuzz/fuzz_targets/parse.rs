@@ -0,0 +1,18 @@
+#![no_main]
+use axre::errors::JsonPayloadError;
+use axre::types::Json;
+use dade_derive::model;
+use libfuzzer_sys::fuzz_target;
+
+#[model]
+struct User {
+    #[field(min_length = 1, max_length = 10)]
+    name: String,
+    tags: Vec<String>,
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Err(JsonPayloadError::Panic) = Json::<User>::parse_fuzz(data) {
+        panic!("parser panicked");
+    }
+});
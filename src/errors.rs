@@ -1,22 +1,454 @@
-use ntex::http::StatusCode;
-use ntex::web::error::PayloadError;
-use ntex::web::WebResponseError;
+use crate::types::body::UnreadBody;
+use crate::types::header::parse_qualities;
+use crate::types::json::JsonConfig;
+use ntex::http::header::ACCEPT_LANGUAGE;
+use ntex::http::{ConnectionType, Method, StatusCode};
+use ntex::util::Bytes;
+use ntex::web::error::{PathError, PayloadError};
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
+use std::any::TypeId;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
 // use ntex::web::error::JsonPayloadError;
 
+/// Error of the extractors.
+///
+/// It implements `WebResponseError`, so a handler can return it too, e.g. to reject a
+/// body after its own checks, and it is rendered same as errors of the extractors:
+///
+/// ```rust,ignore
+/// async fn register(user: Json<User>) -> Result<String, JsonPayloadError> {
+///     if user.name == "admin" {
+///         return Err(JsonPayloadError::Rejected {
+///             status: StatusCode::CONFLICT,
+///             reason: "name is taken".to_string(),
+///         });
+///     }
+///     Ok(format!("Registered {}!", user.name))
+/// }
+/// ```
 #[derive(thiserror::Error, Debug)]
 pub enum JsonPayloadError {
     /// Payload size is bigger than allowed. (default: 32kB)
-    #[error("Json payload size is bigger than allowed")]
-    Overflow,
+    #[error("Json payload size is bigger than allowed limit of {limit} bytes")]
+    Overflow {
+        /// The limit applied to the request
+        limit: usize,
+        /// Length of body declared by `Content-Length`, if it is known to be over limit
+        length: Option<usize>,
+    },
     /// Content type error
     #[error("Content type error")]
     ContentType,
-    /// Deserialize error
-    #[error("Json deserialize error: {0}")]
-    Deserialize(#[from] dade::Error),
+    /// Content type header can not be parsed
+    #[error("Malformed content type: {0}")]
+    MalformedContentType(String),
+    /// Body is not valid base64
+    #[error("Base64 decode error: {0}")]
+    Base64(base64::DecodeError),
+    /// Payload is empty
+    ///
+    /// Every extractor parsing the body fails with it for a body of zero bytes, before
+    /// the body is parsed, so it is never reported as a syntax error. A body of only
+    /// whitespace is not empty and fails to parse. The exceptions are extractors made
+    /// for empty bodies:
+    ///
+    /// | Extractor | Empty body |
+    /// |-----------|------------|
+    /// | [`OptionalJson`](crate::types::OptionalJson) | `None` |
+    /// | [`JsonOrDefault`](crate::types::JsonOrDefault) | default of the model |
+    /// | [`LimitedBytes`](crate::types::LimitedBytes) | empty bytes |
+    ///
+    /// Extractors without a model choose by
+    /// [`JsonConfig::allow_empty_body`](crate::types::json::JsonConfig::allow_empty_body),
+    /// [`LimitedBytes`](crate::types::LimitedBytes) may reject an empty body and
+    /// [`JsonValue`](crate::types::JsonValue) may accept it as `null`.
+    ///
+    /// For [`JsonPart`](crate::types::JsonPart) it applies to the json part too.
+    #[error("Json payload is empty")]
+    EmptyBody,
+    /// Json syntax error, or the body is not of the expected json type
+    #[error("Json syntax error at byte {offset}: {source}")]
+    Syntax {
+        source: serde_json::Error,
+        /// Byte offset of the error in the body
+        offset: usize,
+        /// Leading bytes of the rejected body, if capturing is enabled
+        snippet: Option<Bytes>,
+    },
+    /// Deserialize error, the body is a valid json but violates the model
+    #[error("Json deserialize error: {source}")]
+    Deserialize {
+        source: dade::Error,
+        /// Leading bytes of the rejected body, if capturing is enabled
+        snippet: Option<Bytes>,
+    },
+    /// Field of json object is not of the type of the model, detected while reading
+    #[error("Json field `{field}` is not of type {expected}")]
+    FieldType { field: String, expected: String },
+    /// Json object has a duplicate key
+    #[error("Json object has duplicate key `{0}`")]
+    DuplicateKey(String),
+    /// Operation of json patch is malformed
+    #[error("Json patch operation {index} is invalid: {reason}")]
+    InvalidPatch { index: usize, reason: String },
+    /// Json array has fewer elements than allowed
+    #[error("Json array has {len} elements, fewer than {min}")]
+    TooFew { min: usize, len: usize },
+    /// Json array has more elements than allowed
+    #[error("Json array has {len} elements, more than {max}")]
+    TooMany { max: usize, len: usize },
+    /// Line of newline delimited json is invalid
+    #[error("Json line {line}: {source}")]
+    Line {
+        /// 1-based number of the line
+        line: usize,
+        source: Box<JsonPayloadError>,
+    },
+    /// Element of json array is invalid
+    #[error("Json array element {index}: {source}")]
+    Element {
+        /// 0-based index of the element
+        index: usize,
+        source: Box<JsonPayloadError>,
+    },
+    /// Json value violates the json schema
+    #[error("Json value violates schema: {}", .violations.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))]
+    Schema { violations: Vec<SchemaViolation> },
+    /// Multipart body has no part of the name
+    #[error("Multipart body has no part `{0}`")]
+    MissingPart(String),
+    /// Multipart body is malformed
+    #[error("Malformed multipart body: {0}")]
+    Multipart(String),
+    /// Field of a projection is not a property of the model, see
+    /// [`ProjectedJson`](crate::types::ProjectedJson)
+    #[error("Projected field `{0}` is not a property of the model")]
+    InvalidProjection(String),
+    /// No schema is registered for dynamic validation
+    #[error("No schema is registered for dynamic validation")]
+    MissingSchema,
+    /// Request has a body on a method forbidding it
+    #[error("Request body is not allowed for {0} method")]
+    BodyNotAllowed(Method),
+    /// Request is rejected by a hook
+    #[error("{reason}")]
+    Rejected { status: StatusCode, reason: String },
     /// Payload error
     #[error("Error that occur during reading payload: {0}")]
     Payload(#[from] PayloadError),
+    /// Parser panicked on the body
+    #[error("Json parser panicked")]
+    Panic,
+    /// Parsing on the blocking threadpool failed
+    #[error("Json parse task failed")]
+    Blocking,
+    /// Path parameters error
+    #[error("Path deserialize error: {0}")]
+    Path(#[from] PathError),
+    /// Reading of body is aborted by server shutdown
+    #[error("Server is shutting down")]
+    Shutdown,
+    /// Buffering and parsing of body would allocate more than allowed
+    #[error("Json payload exceeds allocation budget of {budget} bytes")]
+    AllocBudget { budget: usize },
+    /// Body is not read within the timeout
+    #[error("Json payload is not received in time")]
+    ReadTimeout,
+    /// Body is not parsed within the timeout
+    #[error("Json payload is not parsed in time")]
+    ParseTimeout,
+    /// Content encoding is not supported
+    #[error("Unsupported content encoding: {0}")]
+    UnsupportedEncoding(String),
+}
+
+impl JsonPayloadError {
+    /// Leading bytes of the body which failed to deserialize.
+    ///
+    /// Only available when enabled by [`JsonConfig::capture_rejected`](crate::types::json::JsonConfig::capture_rejected).
+    pub fn rejected_snippet(&self) -> Option<&[u8]> {
+        match self {
+            JsonPayloadError::Syntax { snippet, .. }
+            | JsonPayloadError::Deserialize { snippet, .. } => snippet.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Position of a syntax error in the body, as `(line, column, byte offset)`.
+    ///
+    /// Line and column are 1-based, same as reported by `serde_json`.
+    pub fn syntax_position(&self) -> Option<(usize, usize, usize)> {
+        match self {
+            JsonPayloadError::Syntax { source, offset, .. } => {
+                Some((source.line(), source.column(), *offset))
+            }
+            _ => None,
+        }
+    }
+
+    /// Machine readable code of the error, used by json error responses.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            JsonPayloadError::Overflow { .. } => "payload_too_large",
+            JsonPayloadError::ContentType => "unsupported_content_type",
+            JsonPayloadError::MalformedContentType(_) => "malformed_content_type",
+            JsonPayloadError::Base64(_) => "invalid_base64",
+            JsonPayloadError::EmptyBody => "empty_body",
+            JsonPayloadError::Syntax { .. } => "syntax_error",
+            JsonPayloadError::Deserialize { .. } => "validation_error",
+            JsonPayloadError::FieldType { .. } => "invalid_field_type",
+            JsonPayloadError::DuplicateKey(_) => "duplicate_key",
+            JsonPayloadError::InvalidPatch { .. } => "invalid_patch",
+            JsonPayloadError::TooFew { .. } => "too_few_elements",
+            JsonPayloadError::TooMany { .. } => "too_many_elements",
+            JsonPayloadError::Line { source, .. } => source.error_code(),
+            JsonPayloadError::Element { source, .. } => source.error_code(),
+            JsonPayloadError::Schema { .. } => "schema_violation",
+            JsonPayloadError::MissingPart(_) => "missing_part",
+            JsonPayloadError::Multipart(_) => "malformed_multipart",
+            JsonPayloadError::InvalidProjection(_) => "invalid_projection",
+            JsonPayloadError::MissingSchema => "missing_schema",
+            JsonPayloadError::BodyNotAllowed(_) => "body_not_allowed",
+            JsonPayloadError::Rejected { .. } => "rejected",
+            JsonPayloadError::Payload(_) => "payload_error",
+            JsonPayloadError::Panic | JsonPayloadError::Blocking => "internal_error",
+            JsonPayloadError::Path(_) => "path_error",
+            JsonPayloadError::Shutdown => "shutting_down",
+            JsonPayloadError::AllocBudget { .. } => "allocation_budget_exceeded",
+            JsonPayloadError::ReadTimeout => "read_timeout",
+            JsonPayloadError::ParseTimeout => "parse_timeout",
+            JsonPayloadError::UnsupportedEncoding(_) => "unsupported_encoding",
+        }
+    }
+
+    /// Json document describing the error, e.g.
+    /// `{"error":"payload_too_large","limit":262144}`.
+    ///
+    /// Errors other than overflow have `message` with the description of the error,
+    /// syntax errors have `line`, `column` and `offset` of the error in the body,
+    /// errors of a line of newline delimited json have `line` with its number, errors of
+    /// an element of array have `index` with its index, and
+    /// violations of a json schema have `violations` with `path` and `message` of each.
+    pub fn problem(&self) -> serde_json::Value {
+        match self {
+            JsonPayloadError::Schema { violations } => serde_json::json!({
+                "error": self.error_code(),
+                "message": self.to_string(),
+                "violations": violations
+                    .iter()
+                    .map(|v| serde_json::json!({"path": v.path, "message": v.message}))
+                    .collect::<Vec<_>>(),
+            }),
+            JsonPayloadError::Syntax { .. } => {
+                let (line, column, offset) = self.syntax_position().unwrap_or_default();
+                serde_json::json!({
+                    "error": self.error_code(),
+                    "message": self.to_string(),
+                    "line": line,
+                    "column": column,
+                    "offset": offset,
+                })
+            }
+            JsonPayloadError::Line { line, source } => {
+                let mut problem = source.problem();
+                problem["line"] = (*line).into();
+                if problem.get("message").is_some() {
+                    problem["message"] = self.to_string().into();
+                }
+                problem
+            }
+            JsonPayloadError::Element { index, source } => {
+                let mut problem = source.problem();
+                problem["index"] = (*index).into();
+                if problem.get("message").is_some() {
+                    problem["message"] = self.to_string().into();
+                }
+                problem
+            }
+            JsonPayloadError::Overflow { limit, length } => {
+                let mut problem = serde_json::json!({
+                    "error": self.error_code(),
+                    "limit": limit,
+                });
+                if let Some(length) = length {
+                    problem["length"] = (*length).into();
+                }
+                problem
+            }
+            _ => serde_json::json!({
+                "error": self.error_code(),
+                "message": self.to_string(),
+            }),
+        }
+    }
+
+    /// Message of the error in a language accepted by `Accept-Language` of request, if
+    /// [`LocaleMessages`] is registered and has a message for it, or the english one.
+    pub fn localized_message(&self, req: &HttpRequest) -> String {
+        let messages = match JsonConfig::from_req(req).locale_messages {
+            Some(messages) => messages,
+            None => return self.to_string(),
+        };
+        accepted_languages(req)
+            .iter()
+            .find_map(|locale| messages.message(self, locale))
+            .unwrap_or_else(|| self.to_string())
+    }
+
+    /// Create response for the error without a custom rendering of model.
+    ///
+    /// The body is plain text, or a json document of [`problem`](Self::problem) when
+    /// enabled by [`JsonConfig::json_errors`](crate::types::json::JsonConfig::json_errors).
+    /// A json document has `request_id` with the correlation id of request, which is also
+    /// set as `X-Request-Id` header of the response, see [`request_id`].
+    pub fn default_response(&self, req: &HttpRequest) -> HttpResponse {
+        let config = JsonConfig::from_req(req);
+        let status = match self {
+            JsonPayloadError::Deserialize { .. }
+            | JsonPayloadError::FieldType { .. }
+            | JsonPayloadError::DuplicateKey(_)
+            | JsonPayloadError::InvalidPatch { .. }
+            | JsonPayloadError::TooFew { .. }
+            | JsonPayloadError::TooMany { .. }
+            | JsonPayloadError::Schema { .. } => config.validation_status,
+            _ => self.status_code(),
+        };
+        let message = self.localized_message(req);
+        if config.json_errors {
+            let mut problem = self.problem();
+            if problem.get("message").is_some() {
+                problem["message"] = message.into();
+            }
+            let request_id = request_id(req);
+            problem["request_id"] = request_id.clone().into();
+            HttpResponse::build(status)
+                .content_type("application/json; charset=utf-8")
+                .header(X_REQUEST_ID, request_id)
+                .body(problem.to_string())
+        } else {
+            HttpResponse::build(status)
+                .content_type("text/plain; charset=utf-8")
+                .body(message)
+        }
+    }
+}
+
+/// Violation of a json schema, see [`JsonPayloadError::Schema`].
+#[derive(Clone, Debug)]
+pub struct SchemaViolation {
+    /// Json pointer of the invalid value, e.g. `/tags/0`, empty for the whole body
+    pub path: String,
+    /// Description of the violation
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+const X_REQUEST_ID: &str = "x-request-id";
+
+/// Correlation id of request, from `X-Request-Id` header, or a generated one when the
+/// header is missing or is not up to 128 visible ascii characters.
+///
+/// A generated id is random 16 hex digits, it is not stored, so each call generates
+/// a new one for such request.
+pub fn request_id(req: &HttpRequest) -> String {
+    let id = req
+        .headers()
+        .get(X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .map(|id| id.trim())
+        .filter(|id| !id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic()));
+    match id {
+        Some(id) => id.to_string(),
+        None => {
+            static COUNTER: AtomicU64 = AtomicU64::new(0);
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+            format!("{:016x}", hasher.finish())
+        }
+    }
+}
+
+/// Languages of `Accept-Language` header of request, most preferred first.
+fn accepted_languages(req: &HttpRequest) -> Vec<String> {
+    let mut languages: Vec<(String, f32)> = parse_qualities(req.headers(), ACCEPT_LANGUAGE)
+        .into_iter()
+        .filter(|(language, quality)| language != "*" && *quality > 0.0)
+        .collect();
+    languages.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    languages
+        .into_iter()
+        .map(|(language, _)| language)
+        .collect()
+}
+
+/// Localized messages of errors, registered by
+/// [`JsonConfig::locale_messages`](crate::types::json::JsonConfig::locale_messages).
+///
+/// It is asked for each language accepted by the request in order of preference, e.g.
+/// `fr-CA` then `fr`, and the english message of the error is used when no language
+/// has a message. It is implemented for functions:
+///
+/// ```rust,ignore
+/// JsonConfig::default().locale_messages(|err: &JsonPayloadError, locale: &str| {
+///     match (err, locale) {
+///         (JsonPayloadError::EmptyBody, "fr") => Some("Le corps est vide".to_string()),
+///         _ => None,
+///     }
+/// })
+/// ```
+pub trait LocaleMessages: Send + Sync {
+    /// Message of the error in the language, e.g. `fr` or `fr-CA`.
+    fn message(&self, err: &JsonPayloadError, locale: &str) -> Option<String>;
+}
+
+impl<F> LocaleMessages for F
+where
+    F: Fn(&JsonPayloadError, &str) -> Option<String> + Send + Sync,
+{
+    fn message(&self, err: &JsonPayloadError, locale: &str) -> Option<String> {
+        self(err, locale)
+    }
+}
+
+/// Custom rendering of errors for a model.
+///
+/// Implement it for a model and register it by
+/// [`JsonConfig::render_errors`](crate::types::json::JsonConfig::render_errors), then
+/// errors of [`Json`](crate::types::Json) extractor for the model are rendered by it.
+/// Errors of models without a registered rendering use
+/// [`JsonPayloadError::default_response`].
+pub trait JsonErrorRender {
+    /// Create response for the error of extracting this model.
+    fn error_response(err: &JsonPayloadError, req: &HttpRequest) -> HttpResponse {
+        err.default_response(req)
+    }
+}
+
+/// Model whose extraction failed, stored in request extensions.
+pub(crate) struct RejectedModel(pub(crate) TypeId);
+
+/// Function rendering errors of a model.
+pub(crate) type RenderFn = fn(&JsonPayloadError, &HttpRequest) -> HttpResponse;
+
+impl From<dade::Error> for JsonPayloadError {
+    fn from(err: dade::Error) -> Self {
+        JsonPayloadError::Deserialize {
+            source: err,
+            snippet: None,
+        }
+    }
 }
 
 impl From<ntex::http::error::PayloadError> for JsonPayloadError {
@@ -28,10 +460,219 @@ impl From<ntex::http::error::PayloadError> for JsonPayloadError {
 impl WebResponseError for JsonPayloadError {
     fn status_code(&self) -> StatusCode {
         match self {
-            JsonPayloadError::Overflow => StatusCode::INTERNAL_SERVER_ERROR,
+            JsonPayloadError::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
             JsonPayloadError::ContentType => StatusCode::BAD_REQUEST,
-            JsonPayloadError::Deserialize(_) => StatusCode::BAD_REQUEST,
+            JsonPayloadError::MalformedContentType(_) => StatusCode::BAD_REQUEST,
+            JsonPayloadError::Base64(_) => StatusCode::BAD_REQUEST,
+            JsonPayloadError::EmptyBody => StatusCode::BAD_REQUEST,
+            JsonPayloadError::Syntax { .. } => StatusCode::BAD_REQUEST,
+            JsonPayloadError::Deserialize { .. } => StatusCode::BAD_REQUEST,
+            JsonPayloadError::FieldType { .. } => StatusCode::BAD_REQUEST,
+            JsonPayloadError::DuplicateKey(_) => StatusCode::BAD_REQUEST,
+            JsonPayloadError::InvalidPatch { .. } => StatusCode::BAD_REQUEST,
+            JsonPayloadError::TooFew { .. } => StatusCode::BAD_REQUEST,
+            JsonPayloadError::TooMany { .. } => StatusCode::BAD_REQUEST,
+            JsonPayloadError::Line { source, .. } => source.status_code(),
+            JsonPayloadError::Element { source, .. } => source.status_code(),
+            JsonPayloadError::BodyNotAllowed(_) => StatusCode::BAD_REQUEST,
+            JsonPayloadError::Rejected { status, .. } => *status,
             JsonPayloadError::Payload(_) => StatusCode::BAD_REQUEST,
+            JsonPayloadError::Schema { .. } => StatusCode::BAD_REQUEST,
+            JsonPayloadError::MissingPart(_) => StatusCode::BAD_REQUEST,
+            JsonPayloadError::Multipart(_) => StatusCode::BAD_REQUEST,
+            JsonPayloadError::InvalidProjection(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            JsonPayloadError::MissingSchema => StatusCode::INTERNAL_SERVER_ERROR,
+            JsonPayloadError::Panic => StatusCode::INTERNAL_SERVER_ERROR,
+            JsonPayloadError::Blocking => StatusCode::INTERNAL_SERVER_ERROR,
+            JsonPayloadError::Path(_) => StatusCode::NOT_FOUND,
+            JsonPayloadError::Shutdown => StatusCode::SERVICE_UNAVAILABLE,
+            JsonPayloadError::AllocBudget { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            JsonPayloadError::ReadTimeout => StatusCode::REQUEST_TIMEOUT,
+            JsonPayloadError::ParseTimeout => StatusCode::BAD_REQUEST,
+            JsonPayloadError::UnsupportedEncoding(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        }
+    }
+
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        let model = req.extensions().get::<RejectedModel>().map(|m| m.0);
+        let render =
+            model.and_then(|model| JsonConfig::from_req(req).renderers.get(&model).copied());
+        let mut res = match render {
+            Some(render) => render(self, req),
+            None => self.default_response(req),
+        };
+        if req.extensions().get::<UnreadBody>().is_some() {
+            res.head_mut().set_connection_type(ConnectionType::Close);
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use crate::types::{Json, JsonOrDefault, JsonValue, LimitedBytes, OptionalJson};
+    use dade_derive::model;
+    use ntex::http::header::CONTENT_TYPE;
+    use ntex::web::test::{call_service, init_service, read_body, TestRequest};
+    use ntex::web::{self, App, DefaultError, FromRequest};
+
+    #[model]
+    struct User {
+        #[field(min_length = 1, max_length = 10)]
+        name: String,
+    }
+
+    impl Default for User {
+        fn default() -> Self {
+            User {
+                name: "default".to_string(),
+            }
+        }
+    }
+
+    #[ntex::test]
+    async fn test_syntax_position() {
+        let body = "{\n  \"name\": x\n}";
+        let req = request("application/json", body);
+        let e = match extract::<Json<User>>(req).await {
+            Err(e @ JsonPayloadError::Syntax { .. }) => e,
+            _ => panic!("expected a syntax error"),
+        };
+        assert_eq!(e.syntax_position(), Some((2, 11, 12)));
+        assert_eq!(&body[12..13], "x");
+
+        let problem = e.problem();
+        assert_eq!(problem["error"], "syntax_error");
+        assert_eq!(problem["line"], 2);
+        assert_eq!(problem["column"], 11);
+        assert_eq!(problem["offset"], 12);
+    }
+
+    /// Extract `T` from an empty body, describing the value or the code of error.
+    async fn empty<T, F>(config: JsonConfig, describe: F) -> String
+    where
+        T: FromRequest<DefaultError, Error = JsonPayloadError>,
+        F: FnOnce(T) -> String,
+    {
+        let req = request("application/json", "").state(config);
+        match extract::<T>(req).await {
+            Ok(value) => describe(value),
+            Err(e) => e.error_code().to_string(),
+        }
+    }
+
+    #[ntex::test]
+    async fn test_empty_body() {
+        let default = JsonConfig::default;
+        let allow = || JsonConfig::default().allow_empty_body(true);
+        let reject = || JsonConfig::default().allow_empty_body(false);
+        let cases = [
+            (
+                "Json",
+                empty(default(), |_: Json<User>| "value".to_string()).await,
+                "empty_body",
+            ),
+            (
+                "Json, allowed",
+                empty(allow(), |_: Json<User>| "value".to_string()).await,
+                "empty_body",
+            ),
+            (
+                "OptionalJson",
+                empty(default(), |v: OptionalJson<User>| match v.into_inner() {
+                    Some(user) => user.name,
+                    None => "none".to_string(),
+                })
+                .await,
+                "none",
+            ),
+            (
+                "JsonOrDefault",
+                empty(default(), |v: JsonOrDefault<User>| v.into_inner().name).await,
+                "default",
+            ),
+            (
+                "LimitedBytes",
+                empty(default(), |v: LimitedBytes| format!("{} bytes", v.len())).await,
+                "0 bytes",
+            ),
+            (
+                "LimitedBytes, rejected",
+                empty(reject(), |v: LimitedBytes| format!("{} bytes", v.len())).await,
+                "empty_body",
+            ),
+            (
+                "JsonValue",
+                empty(default(), |v: JsonValue| v.to_string()).await,
+                "empty_body",
+            ),
+            (
+                "JsonValue, allowed",
+                empty(allow(), |v: JsonValue| v.to_string()).await,
+                "null",
+            ),
+        ];
+        for (extractor, outcome, expected) in cases {
+            assert_eq!(outcome, expected, "{}", extractor);
+        }
+    }
+
+    async fn register(user: Json<User>) -> Result<String, JsonPayloadError> {
+        if user.name == "admin" {
+            return Err(JsonPayloadError::Rejected {
+                status: StatusCode::CONFLICT,
+                reason: "name is taken".to_string(),
+            });
         }
+        Ok(format!("Registered {}!", user.name))
+    }
+
+    fn register_request(name: &str) -> TestRequest {
+        TestRequest::post()
+            .uri("/")
+            .header(CONTENT_TYPE, "application/json")
+            .header(X_REQUEST_ID, "abc")
+            .set_payload(format!(r#"{{"name":"{}"}}"#, name))
+    }
+
+    #[ntex::test]
+    async fn test_handler_error() {
+        let srv = init_service(App::new().route("/", web::post().to(register))).await;
+
+        let res = call_service(&srv, register_request("axre").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            read_body(res).await,
+            Bytes::from_static(b"Registered axre!")
+        );
+
+        let res = call_service(&srv, register_request("admin").to_request()).await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        assert_eq!(read_body(res).await, Bytes::from_static(b"name is taken"));
+    }
+
+    #[ntex::test]
+    async fn test_handler_error_json() {
+        let srv = init_service(
+            App::new()
+                .app_state(JsonConfig::default().json_errors(true))
+                .route("/", web::post().to(register)),
+        )
+        .await;
+
+        let res = call_service(&srv, register_request("admin").to_request()).await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        assert_eq!(res.headers().get(X_REQUEST_ID).unwrap(), "abc");
+        let problem: serde_json::Value = serde_json::from_slice(&read_body(res).await).unwrap();
+        assert_eq!(
+            problem,
+            serde_json::json!({
+                "error": "rejected",
+                "message": "name is taken",
+                "request_id": "abc",
+            })
+        );
     }
 }
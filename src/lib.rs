@@ -1,2 +1,51 @@
 pub mod errors;
+mod macros;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod prelude;
+pub mod shutdown;
+pub mod test;
 pub mod types;
+pub mod ws;
+
+#[cfg(feature = "macros")]
+pub use axre_macros::json;
+pub use types::body::content_length;
+pub use types::json::{default_json_limit, set_default_json_limit};
+
+/// Items used by [`define_body_extractor`] and `json` attribute, not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use ntex::http::error::PayloadError;
+    pub use ntex::http::{HttpMessage, Payload, StatusCode};
+    pub use ntex::util::Either;
+    pub use ntex::web::error::ErrorContainer;
+    pub use ntex::web::types::Payload as PayloadExtractor;
+    pub use ntex::web::{
+        DefaultError, ErrorRenderer, FromRequest, HttpRequest, HttpResponse, WebResponseError,
+    };
+
+    use crate::types::json::JsonConfig;
+    use ntex::util::{stream_recv, BytesMut};
+
+    /// Get config of the request, the config of the `json` attribute starts from it.
+    pub fn json_config(req: &HttpRequest) -> JsonConfig {
+        JsonConfig::from_req(req)
+    }
+
+    /// Read the whole payload, failing with `None` when it is larger than `limit`.
+    pub async fn read_body(
+        stream: &mut Payload,
+        limit: usize,
+    ) -> Result<BytesMut, Option<PayloadError>> {
+        let mut body = BytesMut::with_capacity(8192);
+        while let Some(chunk) = stream_recv(stream).await {
+            let chunk = chunk.map_err(Some)?;
+            if chunk.len() > limit - body.len() {
+                return Err(None);
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+}
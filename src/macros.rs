@@ -0,0 +1,174 @@
+/// Define extractor for a body format, with its config and error.
+///
+/// Generates the extractor `$name<T>`, the config `$config` registered as app state
+/// with a `limit` builder, and the error `$error` with `Overflow`, `ContentType`,
+/// `Payload` and `Decode` variants. The matcher receives the media type of request,
+/// and the decoder the buffered body, its error is reported as `Decode` by `Display`.
+///
+/// ```rust
+/// use axre::__private::FromRequest;
+/// use ntex::web::{test::TestRequest, DefaultError};
+///
+/// axre::define_body_extractor! {
+///     /// Extractor of `text/csv` bodies as lines.
+///     pub struct Csv, CsvConfig, CsvError;
+///     matches = |mime: &mime::Mime| mime.essence_str() == "text/csv";
+///     decode = |body: &[u8]| {
+///         std::str::from_utf8(body).map(|text| text.lines().map(String::from).collect())
+///     };
+///     bound = std::iter::FromIterator<String>;
+/// }
+///
+/// # ntex::rt::System::new("doc").block_on(async {
+/// let (req, mut payload) = TestRequest::default()
+///     .header("content-type", "text/csv")
+///     .set_payload("a,b\n1,2")
+///     .to_http_parts();
+/// let csv = <Csv<Vec<String>> as FromRequest<DefaultError>>::from_request(&req, &mut payload)
+///     .await
+///     .unwrap();
+/// assert_eq!(csv.into_inner(), ["a,b", "1,2"]);
+/// # });
+/// ```
+///
+/// [`PlainJson`](crate::types::PlainJson) is generated by the macro. [`Json`](crate::types::Json)
+/// is not, since it supports many more options like decoding of `Content-Encoding`, hooks
+/// and validation by models, but the generated extractors behave the same for content
+/// type and limit.
+#[macro_export]
+macro_rules! define_body_extractor {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident, $config:ident, $error:ident;
+        matches = $matches:expr;
+        decode = $decode:expr;
+        bound = $bound:path;
+    ) => {
+        $(#[$meta])*
+        $vis struct $name<T>(pub T);
+
+        impl<T> $name<T> {
+            /// Deconstruct to an inner value
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+
+        impl<T> ::std::ops::Deref for $name<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> ::std::ops::DerefMut for $name<T> {
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+
+        #[doc = concat!("Config for [`", stringify!($name), "`] extractor, registered as app state.")]
+        #[derive(Clone)]
+        $vis struct $config {
+            limit: usize,
+        }
+
+        impl $config {
+            /// Change max size of payload. By default max size is 32Kb
+            pub fn limit(mut self, limit: usize) -> Self {
+                self.limit = limit;
+                self
+            }
+        }
+
+        impl Default for $config {
+            fn default() -> Self {
+                $config { limit: 32_768 }
+            }
+        }
+
+        #[doc = concat!("Error of [`", stringify!($name), "`] extractor.")]
+        #[derive(Debug)]
+        $vis enum $error {
+            /// Payload size is bigger than allowed
+            Overflow { limit: usize },
+            /// Content type error
+            ContentType,
+            /// Payload error
+            Payload($crate::__private::PayloadError),
+            /// Body can not be decoded
+            Decode(String),
+        }
+
+        impl ::std::fmt::Display for $error {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    $error::Overflow { limit } => write!(
+                        f,
+                        "Payload size is bigger than allowed limit of {} bytes",
+                        limit
+                    ),
+                    $error::ContentType => f.write_str("Content type error"),
+                    $error::Payload(e) => {
+                        write!(f, "Error that occur during reading payload: {}", e)
+                    }
+                    $error::Decode(e) => write!(f, "Decode error: {}", e),
+                }
+            }
+        }
+
+        impl ::std::error::Error for $error {}
+
+        impl $crate::__private::WebResponseError for $error {
+            fn status_code(&self) -> $crate::__private::StatusCode {
+                match self {
+                    $error::Overflow { .. } => $crate::__private::StatusCode::PAYLOAD_TOO_LARGE,
+                    _ => $crate::__private::StatusCode::BAD_REQUEST,
+                }
+            }
+        }
+
+        impl<T, Err> $crate::__private::FromRequest<Err> for $name<T>
+        where
+            T: $bound + 'static,
+            Err: $crate::__private::ErrorRenderer,
+        {
+            type Error = $error;
+            type Future = ::std::pin::Pin<
+                Box<dyn ::std::future::Future<Output = Result<Self, Self::Error>>>,
+            >;
+
+            fn from_request(
+                req: &$crate::__private::HttpRequest,
+                payload: &mut $crate::__private::Payload,
+            ) -> Self::Future {
+                use $crate::__private::HttpMessage;
+
+                let limit = req
+                    .app_state::<$config>()
+                    .map_or_else(|| $config::default().limit, |config| config.limit);
+                let matched = match req.mime_type() {
+                    Ok(Some(mime)) => ($matches)(&mime),
+                    _ => false,
+                };
+                if !matched {
+                    return Box::pin(async { Err($error::ContentType) });
+                }
+
+                let mut stream = payload.take();
+                Box::pin(async move {
+                    let body = $crate::__private::read_body(&mut stream, limit)
+                        .await
+                        .map_err(|e| match e {
+                            None => $error::Overflow { limit },
+                            Some(e) => $error::Payload(e),
+                        })?;
+                    ($decode)(&body[..])
+                        .map($name)
+                        .map_err(|e| $error::Decode(e.to_string()))
+                })
+            }
+        }
+    };
+}
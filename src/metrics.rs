@@ -0,0 +1,263 @@
+//! Counters of extraction results, enabled by `metrics` feature.
+//!
+//! Results of all extractions are counted by [`global`] metrics. To count results per
+//! route, register a [`JsonConfig`](crate::types::json::JsonConfig) with its own metrics
+//! for the route.
+//!
+//! Sizes of bodies are observed by a [`SizeHistogram`] of the same metrics, and by
+//! a [`BodySizeSink`] of the config, e.g. to export them to a metrics library:
+//!
+//! ```rust,ignore
+//! let metrics = Arc::new(JsonMetrics::new());
+//! let config = JsonConfig::default().metrics(metrics.clone());
+//! // later, e.g. in a handler exporting metrics
+//! for bucket in metrics.body_sizes().snapshot().buckets {
+//!     println!("le={:?} count={}", bucket.le, bucket.count);
+//! }
+//! ```
+use crate::errors::JsonPayloadError;
+use ntex::util::BytesMut;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+static GLOBAL: JsonMetrics = JsonMetrics::new();
+
+/// Get metrics counting results of all extractions.
+pub fn global() -> &'static JsonMetrics {
+    &GLOBAL
+}
+
+/// Counters of extraction results.
+#[derive(Debug, Default)]
+pub struct JsonMetrics {
+    success: AtomicU64,
+    overflow: AtomicU64,
+    content_type: AtomicU64,
+    parse: AtomicU64,
+    other: AtomicU64,
+    sizes: SizeHistogram,
+}
+
+/// Values of [`JsonMetrics`] counters at some point.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// Number of bodies extracted successfully
+    pub success: u64,
+    /// Number of bodies rejected for its size
+    pub overflow: u64,
+    /// Number of bodies rejected for its content type
+    pub content_type: u64,
+    /// Number of bodies failed to parse or validate
+    pub parse: u64,
+    /// Number of bodies rejected for other reasons, e.g. payload errors
+    pub other: u64,
+}
+
+impl JsonMetrics {
+    /// Create metrics with all counters zero.
+    pub const fn new() -> Self {
+        JsonMetrics {
+            success: AtomicU64::new(0),
+            overflow: AtomicU64::new(0),
+            content_type: AtomicU64::new(0),
+            parse: AtomicU64::new(0),
+            other: AtomicU64::new(0),
+            sizes: SizeHistogram::new(),
+        }
+    }
+
+    /// Histogram of sizes of bodies read by extractions.
+    pub fn body_sizes(&self) -> &SizeHistogram {
+        &self.sizes
+    }
+
+    /// Read current values of counters.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            success: self.success.load(Ordering::Relaxed),
+            overflow: self.overflow.load(Ordering::Relaxed),
+            content_type: self.content_type.load(Ordering::Relaxed),
+            parse: self.parse.load(Ordering::Relaxed),
+            other: self.other.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reset all counters to zero.
+    pub fn reset(&self) {
+        self.success.store(0, Ordering::Relaxed);
+        self.overflow.store(0, Ordering::Relaxed);
+        self.content_type.store(0, Ordering::Relaxed);
+        self.parse.store(0, Ordering::Relaxed);
+        self.other.store(0, Ordering::Relaxed);
+        self.sizes.reset();
+    }
+
+    /// Count result of an extraction.
+    pub(crate) fn record<T>(&self, res: &Result<T, JsonPayloadError>) {
+        let counter = match res {
+            Ok(_) => &self.success,
+            Err(JsonPayloadError::Overflow { .. }) => &self.overflow,
+            Err(JsonPayloadError::ContentType) | Err(JsonPayloadError::MalformedContentType(_)) => {
+                &self.content_type
+            }
+            Err(JsonPayloadError::EmptyBody)
+            | Err(JsonPayloadError::Base64(_))
+            | Err(JsonPayloadError::Syntax { .. })
+            | Err(JsonPayloadError::Deserialize { .. })
+            | Err(JsonPayloadError::FieldType { .. })
+            | Err(JsonPayloadError::DuplicateKey(_))
+            | Err(JsonPayloadError::InvalidPatch { .. })
+            | Err(JsonPayloadError::TooFew { .. })
+            | Err(JsonPayloadError::TooMany { .. })
+            | Err(JsonPayloadError::Line { .. })
+            | Err(JsonPayloadError::Element { .. })
+            | Err(JsonPayloadError::Schema { .. })
+            | Err(JsonPayloadError::MissingPart(_))
+            | Err(JsonPayloadError::Multipart(_)) => &self.parse,
+            Err(_) => &self.other,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Sink of sizes of request bodies observed by extractors, e.g. an adapter to
+/// a histogram of a metrics library, registered by
+/// [`JsonConfig::body_size_sink`](crate::types::json::JsonConfig::body_size_sink).
+///
+/// The size is the length of the body read successfully, after decoding. For a body
+/// rejected for its size, it is the declared `Content-Length` if any, otherwise the
+/// number of bytes read before the body was rejected. Other failures are not observed.
+pub trait BodySizeSink: Send + Sync {
+    /// Observe size of a body in bytes.
+    fn observe(&self, size: usize);
+}
+
+/// Upper bounds of buckets of [`SizeHistogram`], the last bucket is unbounded.
+const BOUNDS: [u64; 10] = [
+    256, 1_024, 4_096, 16_384, 65_536, 262_144, 1_048_576, 4_194_304, 16_777_216, 67_108_864,
+];
+
+/// Histogram of body sizes, with buckets growing by four times from 256 bytes to 64Mb.
+#[derive(Debug, Default)]
+pub struct SizeHistogram {
+    buckets: [AtomicU64; BOUNDS.len() + 1],
+    sum: AtomicU64,
+}
+
+/// Bucket of [`SizeSnapshot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SizeBucket {
+    /// Inclusive upper bound of sizes in the bucket, `None` for the last bucket
+    pub le: Option<u64>,
+    /// Number of bodies in the bucket only, not cumulative
+    pub count: u64,
+}
+
+/// Values of [`SizeHistogram`] at some point.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SizeSnapshot {
+    /// Buckets in order of their bounds
+    pub buckets: Vec<SizeBucket>,
+    /// Number of observed bodies
+    pub count: u64,
+    /// Total size of observed bodies
+    pub sum: u64,
+}
+
+impl SizeHistogram {
+    /// Create histogram with all buckets empty.
+    pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const EMPTY: AtomicU64 = AtomicU64::new(0);
+        SizeHistogram {
+            buckets: [EMPTY; BOUNDS.len() + 1],
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    /// Read current values of buckets.
+    pub fn snapshot(&self) -> SizeSnapshot {
+        let buckets: Vec<SizeBucket> = self
+            .buckets
+            .iter()
+            .enumerate()
+            .map(|(i, bucket)| SizeBucket {
+                le: BOUNDS.get(i).copied(),
+                count: bucket.load(Ordering::Relaxed),
+            })
+            .collect();
+        SizeSnapshot {
+            count: buckets.iter().map(|bucket| bucket.count).sum(),
+            buckets,
+            sum: self.sum.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Empty all buckets.
+    pub fn reset(&self) {
+        for bucket in &self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+        self.sum.store(0, Ordering::Relaxed);
+    }
+}
+
+impl BodySizeSink for SizeHistogram {
+    fn observe(&self, size: usize) {
+        let size = size as u64;
+        let i = BOUNDS.partition_point(|&bound| bound < size);
+        self.buckets[i].fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(size, Ordering::Relaxed);
+    }
+}
+
+/// Recorder of the size of a body read by an extractor into the global metrics, and
+/// metrics and sink of its config.
+pub(crate) struct SizeRecorder {
+    metrics: Option<Arc<JsonMetrics>>,
+    sink: Option<Arc<dyn BodySizeSink>>,
+    read: Rc<Cell<usize>>,
+}
+
+impl SizeRecorder {
+    pub(crate) fn new(
+        metrics: Option<Arc<JsonMetrics>>,
+        sink: Option<Arc<dyn BodySizeSink>>,
+    ) -> Self {
+        SizeRecorder {
+            metrics,
+            sink,
+            read: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Inspector of body chunks counting bytes read.
+    pub(crate) fn counter(&self) -> impl FnMut(&[u8]) -> Result<(), JsonPayloadError> {
+        let read = self.read.clone();
+        move |chunk: &[u8]| {
+            read.set(read.get() + chunk.len());
+            Ok(())
+        }
+    }
+
+    /// Observe size of the body read, or rejected for its size.
+    pub(crate) fn record(&self, res: &Result<BytesMut, JsonPayloadError>) {
+        let size = match res {
+            Ok(body) => body.len(),
+            Err(JsonPayloadError::Overflow {
+                length: Some(len), ..
+            }) => *len,
+            Err(JsonPayloadError::Overflow { length: None, .. }) => self.read.get(),
+            Err(_) => return,
+        };
+        GLOBAL.sizes.observe(size);
+        if let Some(ref metrics) = self.metrics {
+            metrics.sizes.observe(size);
+        }
+        if let Some(ref sink) = self.sink {
+            sink.observe(size);
+        }
+    }
+}
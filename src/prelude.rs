@@ -0,0 +1,22 @@
+//! Common imports of the crate.
+//!
+//! ```rust,ignore
+//! use axre::prelude::*;
+//! ```
+pub use crate::errors::{JsonErrorRender, JsonPayloadError, LocaleMessages};
+pub use crate::shutdown::ShutdownSignal;
+pub use crate::types::content_type::{ContentTypePredicate, JsonContentType};
+pub use crate::types::dynamic::DynValidator;
+pub use crate::types::json::JsonConfig;
+pub use crate::types::projection::Projection;
+#[cfg(feature = "json-schema")]
+pub use crate::types::schema::JsonSchemaValidator;
+pub use crate::types::ser::{JsonSerOptions, JsonWithOptions, KeyCase};
+#[cfg(feature = "streaming")]
+pub use crate::types::StreamingJson;
+pub use crate::types::{
+    BlockingJson, BodyInfo, BoundedJson, DynJson, DynSchema, EventStream, Json, JsonLenient,
+    JsonOrDefault, JsonPart, JsonPatch, JsonValue, JsonWithPreview, LimitedBytes, MultiJson,
+    NdJson, NoContent, OneOrMany, OptionalJson, PlainJson, ProjectedJson, RequestMeta, Timed,
+    ValidateOnly, ValidatedBytes, WithPath,
+};
@@ -0,0 +1,114 @@
+//! Abort reading of bodies on server shutdown.
+//!
+//! ```rust,ignore
+//! let shutdown = ShutdownSignal::new();
+//!
+//! let srv = {
+//!     let shutdown = shutdown.clone();
+//!     web::server(move || {
+//!         App::new()
+//!             .app_state(JsonConfig::default().abort_on_shutdown(shutdown.clone()))
+//!             .service(index)
+//!     })
+//!     .bind("127.0.0.1:8080")?
+//!     .run()
+//! };
+//!
+//! // on SIGTERM, abort in-flight reads, then stop gracefully
+//! shutdown.trigger();
+//! srv.stop(true).await;
+//! ```
+use crate::types::BodyStream;
+use ntex::http::error::PayloadError;
+use ntex::time::{interval, Interval, Millis};
+use ntex::util::{Bytes, Stream};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::{error, fmt, io};
+
+/// Signal of server shutdown, shared between the server and
+/// [`JsonConfig::abort_on_shutdown`](crate::types::json::JsonConfig::abort_on_shutdown).
+///
+/// Once triggered, bodies being read fail with
+/// [`JsonPayloadError::Shutdown`](crate::errors::JsonPayloadError::Shutdown) within
+/// 100 milliseconds, instead of waiting for slow clients.
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownSignal(Arc<AtomicBool>);
+
+impl ShutdownSignal {
+    /// Create signal, not triggered yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trigger shutdown.
+    pub fn trigger(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Whether shutdown is triggered.
+    pub fn is_triggered(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Error of payload stream aborted on shutdown.
+#[derive(Debug)]
+pub(crate) struct ShuttingDown;
+
+impl fmt::Display for ShuttingDown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Server is shutting down")
+    }
+}
+
+impl error::Error for ShuttingDown {}
+
+impl ShuttingDown {
+    /// Check whether payload error is caused by shutdown.
+    pub(crate) fn is(err: &PayloadError) -> bool {
+        match err {
+            PayloadError::Io(e) => e.get_ref().map_or(false, |e| e.is::<ShuttingDown>()),
+            _ => false,
+        }
+    }
+}
+
+/// Payload stream failing when shutdown is triggered.
+pub(crate) struct ShutdownAware {
+    stream: BodyStream,
+    signal: ShutdownSignal,
+    check: Interval,
+}
+
+impl ShutdownAware {
+    pub(crate) fn new(stream: BodyStream, signal: ShutdownSignal) -> Self {
+        ShutdownAware {
+            stream,
+            signal,
+            check: interval(Millis(100)),
+        }
+    }
+}
+
+impl Stream for ShutdownAware {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.signal.is_triggered() {
+            let err = io::Error::new(io::ErrorKind::Other, ShuttingDown);
+            return Poll::Ready(Some(Err(PayloadError::Io(err))));
+        }
+
+        match self.stream.as_mut().poll_next(cx) {
+            Poll::Pending => {
+                // make sure to be polled again to check the signal
+                while self.check.poll_tick(cx).is_ready() {}
+                Poll::Pending
+            }
+            poll => poll,
+        }
+    }
+}
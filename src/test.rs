@@ -0,0 +1,94 @@
+//! Helpers for testing extraction of request's body without running a server.
+//!
+//! ```rust,ignore
+//! use axre::test;
+//! use axre::types::Json;
+//!
+//! let req = test::request("application/json", r#"{"name":"axre"}"#);
+//! let user = test::extract::<Json<User>>(req).await.unwrap();
+//! ```
+use ntex::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use ntex::util::Bytes;
+use ntex::web::test::TestRequest;
+use ntex::web::{DefaultError, FromRequest};
+
+/// Create `TestRequest` with given content type and body, and `Content-Length` of the body.
+///
+/// More headers like `Content-Encoding` can be set on the returned request.
+pub fn request<B: Into<Bytes>>(content_type: &str, body: B) -> TestRequest {
+    let body = body.into();
+    TestRequest::default()
+        .header(CONTENT_TYPE, content_type)
+        .header(CONTENT_LENGTH, body.len().to_string())
+        .set_payload(body)
+}
+
+/// Run extractor `T` to completion against the request.
+pub async fn extract<T>(req: TestRequest) -> Result<T, T::Error>
+where
+    T: FromRequest<DefaultError>,
+{
+    let (req, mut payload) = req.to_http_parts();
+    T::from_request(&req, &mut payload).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::JsonPayloadError;
+    use crate::types::{BodyInfo, Json};
+    use dade_derive::model;
+    use ntex::http::header::CONTENT_ENCODING;
+
+    #[model]
+    struct User {
+        #[field(min_length = 1, max_length = 10)]
+        name: String,
+    }
+
+    #[ntex::test]
+    async fn test_extract() {
+        let req = request("application/json", r#"{"name":"axre"}"#);
+        let user = extract::<Json<User>>(req).await.unwrap();
+        assert_eq!(user.name, "axre");
+
+        let req = request("application/json", r#"{"name":""}"#);
+        let res = extract::<Json<User>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::Deserialize { .. })));
+
+        let req = request("text/html", r#"{"name":"axre"}"#);
+        let res = extract::<Json<User>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::ContentType)));
+    }
+
+    #[ntex::test]
+    async fn test_extract_identity_encoding() {
+        let req =
+            request("application/json", r#"{"name":"axre"}"#).header(CONTENT_ENCODING, "identity");
+        let (req, mut payload) = req.to_http_parts();
+        let user = <Json<User> as FromRequest<DefaultError>>::from_request(&req, &mut payload)
+            .await
+            .unwrap();
+        assert_eq!(user.name, "axre");
+        assert!(!BodyInfo::of(&req).unwrap().was_compressed());
+    }
+
+    #[cfg(feature = "compress")]
+    #[ntex::test]
+    async fn test_extract_gzip_encoding() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(br#"{"name":"axre"}"#).unwrap();
+        let req =
+            request("application/json", encoder.finish().unwrap()).header(CONTENT_ENCODING, "gzip");
+        let (req, mut payload) = req.to_http_parts();
+        let user = <Json<User> as FromRequest<DefaultError>>::from_request(&req, &mut payload)
+            .await
+            .unwrap();
+        assert_eq!(user.name, "axre");
+        assert!(BodyInfo::of(&req).unwrap().was_compressed());
+    }
+}
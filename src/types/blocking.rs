@@ -0,0 +1,76 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Json extractor parsing the body on the blocking thread pool, for `Send` models.
+///
+/// The body is checked and validated same as [`Json`](crate::types::Json), but bodies
+/// larger than `ABOVE` bytes are parsed on the blocking thread pool, so that parsing of
+/// huge payloads does not block the event loop. By default every body is. Parsing on
+/// the thread pool costs a hand-off between threads, so smaller bodies are better parsed
+/// inline. [`Json`](crate::types::Json) and the other extractors always parse inline,
+/// since their models need not be `Send`.
+///
+/// ```rust,ignore
+/// // bodies up to 1Mb are parsed inline
+/// web::resource("/import")
+///     .app_state(JsonConfig::default().limit_mb(64))
+///     .route(web::post().to(|rows: BlockingJson<Rows, 1_048_576>| async move {
+///         rows.len().to_string()
+///     }))
+/// ```
+///
+/// With [`JsonConfig::parse_timeout`] every body is parsed on the blocking thread pool,
+/// and the extractor stops waiting for it once the timeout elapses.
+pub struct BlockingJson<T, const ABOVE: usize = 0>(pub T);
+
+impl<T, const ABOVE: usize> BlockingJson<T, ABOVE> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T, const ABOVE: usize> ops::Deref for BlockingJson<T, ABOVE> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T, const ABOVE: usize> ops::DerefMut for BlockingJson<T, ABOVE> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T, Err: ErrorRenderer, const ABOVE: usize> FromRequest<Err> for BlockingJson<T, ABOVE>
+where
+    T: Model + Send + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req).for_model::<T>();
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) => config.parse_blocking(&req, body, ABOVE).await,
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(BlockingJson)
+        })
+    }
+}
@@ -0,0 +1,678 @@
+use crate::errors::JsonPayloadError;
+use crate::shutdown::{ShutdownAware, ShuttingDown};
+use crate::types::content_type::media_version;
+use crate::types::json::JsonConfig;
+#[cfg(feature = "compress")]
+use ntex::http::encoding::Decoder;
+use ntex::http::error::PayloadError;
+#[cfg(feature = "compress")]
+use ntex::http::header::ContentEncoding;
+use ntex::http::header::{HeaderMap, CONTENT_ENCODING};
+use ntex::http::{HttpMessage, Payload};
+use ntex::time::{timeout, Millis};
+use ntex::util::{stream_recv, Bytes, BytesMut, Stream};
+use ntex::web::HttpRequest;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Stream of request's payload.
+pub type BodyStream = Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>>;
+
+/// Decoder for a custom content encoding, see [`JsonConfig::decoder`](crate::types::json::JsonConfig::decoder).
+pub(crate) type DecoderFn = Arc<dyn Fn(BodyStream) -> BodyStream + Send + Sync>;
+
+/// Inspector of body chunks, see [`collect_body`].
+pub(crate) type InspectFn = Box<dyn FnMut(&[u8]) -> Result<(), JsonPayloadError>>;
+
+/// Information about how the body of request was read by an extractor.
+///
+/// The extractors store it in request extensions, so a handler can read it
+/// with the request after extraction:
+///
+/// ```rust,ignore
+/// async fn index(req: HttpRequest, user: Json<User>) -> String {
+///     let compressed = BodyInfo::of(&req).map_or(false, |info| info.was_compressed());
+///     format!("Welcome {}!", user.name)
+/// }
+/// ```
+///
+/// The version of a vendor media type of request is captured, so a handler can branch
+/// on the version of api, e.g. `3` for `application/vnd.api.v3+json`. [`Json`] holds the
+/// model only, so the version is read from here:
+///
+/// ```rust,ignore
+/// async fn index(req: HttpRequest, user: Json<User>) -> String {
+///     match BodyInfo::of(&req).and_then(|info| info.media_version()) {
+///         Some(3) => format!("Welcome {}!", user.name),
+///         _ => format!("Hello {}!", user.name),
+///     }
+/// }
+/// ```
+///
+/// [`Json`]: crate::types::Json
+///
+/// Trailer headers of chunked bodies are not available here or anywhere else, since
+/// ntex discards them while decoding the payload, before the extractor reads it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BodyInfo {
+    compressed: bool,
+    limit: usize,
+    version: Option<u32>,
+}
+
+impl BodyInfo {
+    /// Get information stored by the extractor, `None` if no body was read.
+    pub fn of(req: &HttpRequest) -> Option<BodyInfo> {
+        req.extensions().get::<BodyInfo>().copied()
+    }
+
+    /// Whether the body was decoded according to `Content-Encoding` header.
+    pub fn was_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// Max size of payload applied to the request, resolved from config or default.
+    pub fn effective_limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Version encoded in the media type of request, see
+    /// [`media_version`](crate::types::content_type::media_version).
+    pub fn media_version(&self) -> Option<u32> {
+        self.version
+    }
+}
+
+/// Take payload of request, decoding it according to `Content-Encoding` header.
+///
+/// With [`JsonConfig::wire_limit`] the stream fails as soon as more bytes than the limit
+/// are read from the wire, before decoding. With [`JsonConfig::abort_on_shutdown`] the
+/// stream fails once shutdown is triggered.
+pub(crate) fn take_payload(
+    req: &HttpRequest,
+    payload: &mut Payload,
+    config: &JsonConfig,
+) -> Result<BodyStream, JsonPayloadError> {
+    let stream: BodyStream = match config.wire_limit {
+        Some(limit) => Box::pin(WireLimited {
+            stream: payload.take(),
+            remaining: limit,
+        }),
+        None => Box::pin(payload.take()),
+    };
+
+    let encoding = content_encoding(req.headers())?;
+    req.extensions_mut().insert(BodyInfo {
+        compressed: encoding.is_some(),
+        limit: config.limit,
+        version: req
+            .mime_type()
+            .ok()
+            .flatten()
+            .and_then(|mime| media_version(&mime)),
+    });
+
+    // small bodies are decoded at once when they are read, limited to the max size
+    let at_once = content_length(req)
+        .filter(|len| *len < config.decode_at_once_below)
+        .map(|_| config.limit);
+    let stream = match encoding {
+        None => stream,
+        Some(encoding) => match config.decoders.get(&encoding) {
+            Some(decoder) => decoder(stream),
+            None => builtin_decoder(stream, &encoding, at_once)?,
+        },
+    };
+
+    match config.shutdown {
+        Some(ref signal) => Ok(Box::pin(ShutdownAware::new(stream, signal.clone()))),
+        None => Ok(stream),
+    }
+}
+
+/// Payload stream failing with `PayloadError::Overflow` when too many bytes are read.
+struct WireLimited {
+    stream: Payload,
+    remaining: usize,
+}
+
+impl Stream for WireLimited {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match Pin::new(&mut self.stream).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                if chunk.len() > self.remaining {
+                    Poll::Ready(Some(Err(PayloadError::Overflow)))
+                } else {
+                    self.remaining -= chunk.len();
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+            }
+            poll => poll,
+        }
+    }
+}
+
+/// Decode stream by encodings of `compress` feature.
+///
+/// With `at_once`, gzip and deflate bodies are buffered compressed and decoded in one
+/// pass, up to `at_once` bytes of output, see [`DecodeAtOnce`].
+#[cfg(feature = "compress")]
+fn builtin_decoder(
+    stream: BodyStream,
+    encoding: &str,
+    at_once: Option<usize>,
+) -> Result<BodyStream, JsonPayloadError> {
+    let encoding = match encoding {
+        "gzip" | "x-gzip" => ContentEncoding::Gzip,
+        "deflate" => ContentEncoding::Deflate,
+        "br" => ContentEncoding::Br,
+        _ => return Err(JsonPayloadError::UnsupportedEncoding(encoding.to_string())),
+    };
+    match (encoding, at_once) {
+        (ContentEncoding::Gzip | ContentEncoding::Deflate, Some(limit)) => {
+            Ok(Box::pin(DecodeAtOnce {
+                stream,
+                encoding,
+                limit,
+                body: BytesMut::new(),
+                done: false,
+            }))
+        }
+        _ => Ok(Box::pin(Decoder::new(stream, encoding))),
+    }
+}
+
+/// Without `compress` feature there are no built-in encodings, so the body is rejected
+/// with [`JsonPayloadError::UnsupportedEncoding`] instead of parsing compressed bytes.
+#[cfg(not(feature = "compress"))]
+fn builtin_decoder(
+    _: BodyStream,
+    encoding: &str,
+    _: Option<usize>,
+) -> Result<BodyStream, JsonPayloadError> {
+    Err(JsonPayloadError::UnsupportedEncoding(encoding.to_string()))
+}
+
+/// Stream buffering the whole compressed body, then decoding it in one pass into a
+/// single chunk.
+///
+/// This saves the per-chunk overhead of the streaming decoder for small bodies, see
+/// `benches/decode.rs`. The output is cut after `limit + 1` bytes, so a small body
+/// inflating to a huge one is still detected as overflow without decoding all of it.
+#[cfg(feature = "compress")]
+struct DecodeAtOnce {
+    stream: BodyStream,
+    encoding: ContentEncoding,
+    limit: usize,
+    body: BytesMut,
+    done: bool,
+}
+
+#[cfg(feature = "compress")]
+impl Stream for DecodeAtOnce {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        use flate2::read::{DeflateDecoder, GzDecoder};
+        use std::io::Read;
+
+        if self.done {
+            return Poll::Ready(None);
+        }
+        loop {
+            match self.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => self.body.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => break,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        self.done = true;
+        let take = self.limit as u64 + 1;
+        let mut decoded = Vec::new();
+        let res = match self.encoding {
+            ContentEncoding::Gzip => GzDecoder::new(&self.body[..])
+                .take(take)
+                .read_to_end(&mut decoded),
+            _ => DeflateDecoder::new(&self.body[..])
+                .take(take)
+                .read_to_end(&mut decoded),
+        };
+        match res {
+            Ok(_) => Poll::Ready(Some(Ok(Bytes::from(decoded)))),
+            Err(_) => Poll::Ready(Some(Err(PayloadError::EncodingCorrupted))),
+        }
+    }
+}
+
+/// Read the whole stream into buffer, failing with [`JsonPayloadError::Overflow`]
+/// as soon as the body grows larger than `limit`, or the stream reads more than
+/// `wire_limit` bytes from the wire.
+///
+/// Chunks are appended once at least `coalesce` bytes are pending, see
+/// [`JsonConfig::coalesce`]. Each chunk is passed to `inspect` as it arrives, which
+/// may abort reading with an error.
+///
+/// The buffer is allocated for `length` declared by `Content-Length` upfront, up to
+/// 64Kb so a client can not make it allocate the limit without sending the body. It
+/// grows by at least doubling, but never beyond `limit`. So it is reallocated a few
+/// times only, however the body is split into chunks.
+///
+/// With `budget`, reading fails with [`JsonPayloadError::AllocBudget`] as soon as the
+/// capacity of the buffer plus the length of the body exceeds it, the length accounting
+/// for parsing, see [`JsonConfig::alloc_budget`].
+pub(crate) async fn collect_body(
+    mut stream: BodyStream,
+    limit: usize,
+    length: Option<usize>,
+    wire_limit: Option<usize>,
+    coalesce: usize,
+    mut inspect: Option<InspectFn>,
+    budget: Option<usize>,
+) -> Result<BytesMut, JsonPayloadError> {
+    let mut body = BytesMut::with_capacity(length.unwrap_or(8192).min(65_536).min(limit));
+    let mut pending = Vec::new();
+    let mut pending_len = 0;
+
+    while let Some(item) = stream_recv(&mut stream).await {
+        let chunk = match item {
+            Ok(chunk) => chunk,
+            Err(PayloadError::Overflow) => {
+                return Err(JsonPayloadError::Overflow {
+                    limit: wire_limit.unwrap_or(limit),
+                    length: None,
+                })
+            }
+            Err(e) if ShuttingDown::is(&e) => return Err(JsonPayloadError::Shutdown),
+            Err(e) => return Err(e.into()),
+        };
+        // chunks are decoded output with `compress` feature, so a single chunk may be
+        // much larger than bytes read from the wire. check it before appending,
+        // `body.len()` never exceeds `limit` here.
+        if chunk.len() > limit - body.len() - pending_len {
+            return Err(JsonPayloadError::Overflow {
+                limit,
+                length: None,
+            });
+        }
+        if let Some(budget) = budget {
+            let len = body.len() + pending_len + chunk.len();
+            if capacity_for(&body, len - body.len(), limit) + len > budget {
+                return Err(JsonPayloadError::AllocBudget { budget });
+            }
+        }
+        if let Some(ref mut inspect) = inspect {
+            inspect(&chunk)?;
+        }
+        if coalesce == 0 {
+            grow(&mut body, chunk.len(), limit);
+            body.extend_from_slice(&chunk);
+        } else {
+            pending_len += chunk.len();
+            pending.push(chunk);
+            if pending_len >= coalesce {
+                append(&mut body, &mut pending, &mut pending_len, limit);
+            }
+        }
+    }
+    append(&mut body, &mut pending, &mut pending_len, limit);
+
+    Ok(body)
+}
+
+/// Read body by `fut`, failing with [`JsonPayloadError::ReadTimeout`] unless it is read
+/// within `read_timeout`, see [`JsonConfig::read_timeout`].
+pub(crate) async fn read_within<F>(
+    fut: F,
+    read_timeout: Option<Millis>,
+) -> Result<BytesMut, JsonPayloadError>
+where
+    F: Future<Output = Result<BytesMut, JsonPayloadError>>,
+{
+    match read_timeout {
+        Some(read_timeout) => timeout(read_timeout, fut)
+            .await
+            .unwrap_or(Err(JsonPayloadError::ReadTimeout)),
+        None => fut.await,
+    }
+}
+
+/// Append pending chunks to body, reserving space for all of them at once.
+fn append(body: &mut BytesMut, pending: &mut Vec<Bytes>, pending_len: &mut usize, limit: usize) {
+    grow(body, *pending_len, limit);
+    for chunk in pending.drain(..) {
+        body.extend_from_slice(&chunk);
+    }
+    *pending_len = 0;
+}
+
+/// Reserve space for `additional` bytes, at least doubling capacity but not beyond
+/// `limit`. `body.len() + additional` never exceeds `limit` here.
+fn grow(body: &mut BytesMut, additional: usize, limit: usize) {
+    let capacity = capacity_for(body, additional, limit);
+    if capacity > body.capacity() {
+        body.reserve(capacity - body.len());
+    }
+}
+
+/// Capacity of body after growing it for `additional` bytes, see [`grow`].
+fn capacity_for(body: &BytesMut, additional: usize, limit: usize) -> usize {
+    if body.capacity() - body.len() < additional {
+        (body.capacity() * 2)
+            .max(body.len() + additional)
+            .min(limit)
+    } else {
+        body.capacity()
+    }
+}
+
+/// Marker of request whose body was not read to the end, see [`mark_unread`].
+pub(crate) struct UnreadBody;
+
+/// Mark request whose extraction failed before its body was read to the end.
+///
+/// The error response then closes the connection, since the rest of the body is left
+/// on it and would be read as the next request of keep-alive connection. Draining the
+/// body instead would let a client keep the worker busy with an endless body.
+pub(crate) fn mark_unread(req: &HttpRequest, err: &JsonPayloadError) {
+    let unread = matches!(
+        err,
+        JsonPayloadError::Overflow { .. }
+            | JsonPayloadError::ContentType
+            | JsonPayloadError::MalformedContentType(_)
+            | JsonPayloadError::Payload(_)
+            | JsonPayloadError::Shutdown
+            | JsonPayloadError::ReadTimeout
+            | JsonPayloadError::AllocBudget { .. }
+            | JsonPayloadError::FieldType { .. }
+            | JsonPayloadError::BodyNotAllowed(_)
+            | JsonPayloadError::Rejected { .. }
+            | JsonPayloadError::InvalidProjection(_)
+            | JsonPayloadError::MissingSchema
+            | JsonPayloadError::UnsupportedEncoding(_)
+            | JsonPayloadError::Path(_)
+    );
+    if unread && has_body(req) {
+        req.extensions_mut().insert(UnreadBody);
+    }
+}
+
+/// Get length of body declared by `Content-Length` header of request, without reading
+/// the body, e.g. for a middleware routing by size.
+///
+/// `None` when the header is missing or is not a valid length, same as the extractors
+/// treat it.
+pub fn content_length(req: &HttpRequest) -> Option<usize> {
+    req.headers()
+        .get("content-length")
+        .and_then(|l| l.to_str().ok())
+        .and_then(|s| s.parse::<usize>().ok())
+}
+
+/// Check whether request declares a body, either by a non-zero `Content-Length`
+/// or by `Transfer-Encoding`.
+pub(crate) fn has_body(req: &HttpRequest) -> bool {
+    match content_length(req) {
+        Some(len) => len > 0,
+        None => req.headers().contains_key("transfer-encoding"),
+    }
+}
+
+/// Resolve the encoding of request's payload from `Content-Encoding` header, `None`
+/// for `identity`.
+///
+/// Supported encodings are `identity`, encodings registered by
+/// [`JsonConfig::decoder`](crate::types::json::JsonConfig::decoder), and with `compress`
+/// feature `gzip`, `deflate` and `br`, which are unsupported without the feature.
+///
+/// The header may list several comma-separated encodings, but only a single
+/// non-`identity` encoding is supported, e.g. `identity, gzip` is same as `gzip`.
+/// Layered encodings like `gzip, br` are rejected with
+/// [`JsonPayloadError::UnsupportedEncoding`].
+pub(crate) fn content_encoding(headers: &HeaderMap) -> Result<Option<String>, JsonPayloadError> {
+    let mut encoding = None;
+    for value in headers.get_all(CONTENT_ENCODING) {
+        let value = value.to_str().map_err(|_| {
+            JsonPayloadError::UnsupportedEncoding(
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })?;
+        for token in value.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let token = token.to_ascii_lowercase();
+            if token == "identity" {
+                continue;
+            }
+            if encoding.is_some() {
+                return Err(JsonPayloadError::UnsupportedEncoding(value.to_string()));
+            }
+            encoding = Some(token);
+        }
+    }
+    Ok(encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use crate::types::Json;
+    use dade_derive::model;
+    use ntex::http::header::HeaderValue;
+    use ntex::http::{ConnectionType, StatusCode};
+    use ntex::web::test::TestRequest;
+    use ntex::web::{DefaultError, FromRequest, WebResponseError};
+
+    #[model]
+    struct User {
+        name: String,
+    }
+
+    /// Headers with a `Content-Encoding` header for each of values.
+    fn encodings(values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(CONTENT_ENCODING, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_content_encoding() {
+        let encoding = |values: &[&'static str]| content_encoding(&encodings(values));
+
+        assert_eq!(encoding(&[]).unwrap(), None);
+        assert_eq!(encoding(&["identity"]).unwrap(), None);
+        assert_eq!(encoding(&["Identity, identity"]).unwrap(), None);
+        assert_eq!(encoding(&["GZIP"]).unwrap().as_deref(), Some("gzip"));
+        assert_eq!(
+            encoding(&["identity, gzip"]).unwrap().as_deref(),
+            Some("gzip")
+        );
+        assert_eq!(
+            encoding(&["identity", "br"]).unwrap().as_deref(),
+            Some("br")
+        );
+        assert_eq!(
+            encoding(&[" deflate ,"]).unwrap().as_deref(),
+            Some("deflate")
+        );
+
+        for layered in [&["gzip, br"][..], &["gzip", "br"][..], &["gzip, gzip"][..]] {
+            assert!(matches!(
+                encoding(layered),
+                Err(JsonPayloadError::UnsupportedEncoding(_))
+            ));
+        }
+    }
+
+    #[ntex::test]
+    async fn test_unsupported_encoding() {
+        for encoding in ["gzip, br", "identity, gzip, deflate", "zstd"] {
+            let req = request("application/json", r#"{"name":"axre"}"#)
+                .header(CONTENT_ENCODING, encoding);
+            match extract::<Json<User>>(req).await {
+                Err(e @ JsonPayloadError::UnsupportedEncoding(_)) => {
+                    assert_eq!(e.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                }
+                _ => panic!("expected unsupported encoding {}", encoding),
+            }
+        }
+    }
+
+    #[cfg(feature = "compress")]
+    #[ntex::test]
+    async fn test_layered_identity() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(br#"{"name":"axre"}"#).unwrap();
+        let req = request("application/json", encoder.finish().unwrap())
+            .header(CONTENT_ENCODING, "identity, gzip");
+        let user = extract::<Json<User>>(req).await.unwrap();
+        assert_eq!(user.name, "axre");
+    }
+
+    #[cfg(not(feature = "compress"))]
+    #[ntex::test]
+    async fn test_encoding_without_compress() {
+        for encoding in ["gzip", "deflate", "br"] {
+            let req = request("application/json", r#"{"name":"axre"}"#)
+                .header(CONTENT_ENCODING, encoding);
+            match extract::<Json<User>>(req).await {
+                Err(e @ JsonPayloadError::UnsupportedEncoding(_)) => {
+                    assert_eq!(e.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                }
+                _ => panic!("expected unsupported encoding {}", encoding),
+            }
+        }
+    }
+
+    /// Stream of `count` chunks of a single byte.
+    struct Bytewise(usize);
+
+    impl Stream for Bytewise {
+        type Item = Result<Bytes, PayloadError>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            if self.0 == 0 {
+                return Poll::Ready(None);
+            }
+            self.0 -= 1;
+            Poll::Ready(Some(Ok(Bytes::from_static(b"x"))))
+        }
+    }
+
+    #[ntex::test]
+    async fn test_collect_bytewise() {
+        let limit = 65_536;
+        for coalesce in [0, 1024] {
+            let stream = Box::pin(Bytewise(limit - 1));
+            let body = collect_body(stream, limit, None, None, coalesce, None, None)
+                .await
+                .unwrap();
+            assert_eq!(body.len(), limit - 1);
+            assert!(body.capacity() <= limit);
+
+            let stream = Box::pin(Bytewise(limit + 1));
+            let res = collect_body(stream, limit, None, None, coalesce, None, None).await;
+            assert!(matches!(res, Err(JsonPayloadError::Overflow { .. })));
+        }
+    }
+
+    #[test]
+    fn test_bounded_growth() {
+        // same steps as `collect_body` with `coalesce` for a body of single bytes
+        let limit = 65_536;
+        let coalesce = 1024;
+        let mut body = BytesMut::with_capacity(8192);
+        let mut pending = Vec::new();
+        let mut pending_len = 0;
+        let mut reallocs = 0;
+        for _ in 0..limit - 1 {
+            pending.push(Bytes::from_static(b"x"));
+            pending_len += 1;
+            if pending_len >= coalesce {
+                let capacity = body.capacity();
+                append(&mut body, &mut pending, &mut pending_len, limit);
+                if body.capacity() != capacity {
+                    reallocs += 1;
+                }
+            }
+        }
+        append(&mut body, &mut pending, &mut pending_len, limit);
+
+        assert_eq!(body.len(), limit - 1);
+        assert!(body.capacity() <= limit);
+        // 8Kb doubled up to 64Kb
+        assert!(reallocs <= 3, "reallocated {} times", reallocs);
+    }
+
+    /// Extract `Json<User>` and get `BodyInfo` stored for the request.
+    async fn body_info(req: TestRequest) -> (Result<Json<User>, JsonPayloadError>, BodyInfo) {
+        let (req, mut payload) = req.to_http_parts();
+        let res = <Json<User> as FromRequest<DefaultError>>::from_request(&req, &mut payload).await;
+        (res, BodyInfo::of(&req).unwrap())
+    }
+
+    #[ntex::test]
+    async fn test_effective_limit() {
+        let req = request("application/json", r#"{"name":"axre"}"#);
+        let (res, info) = body_info(req).await;
+        assert!(res.is_ok());
+        assert_eq!(
+            info.effective_limit(),
+            crate::types::json::default_json_limit()
+        );
+
+        let config = JsonConfig::default().limit(64);
+        let req = request("application/json", r#"{"name":"axre"}"#).state(config);
+        let (res, info) = body_info(req).await;
+        assert!(res.is_ok());
+        assert_eq!(info.effective_limit(), 64);
+
+        let config = JsonConfig::default().limit(8);
+        let req = request("application/json", r#"{"name":"axre"}"#).state(config);
+        let (res, info) = body_info(req).await;
+        assert!(matches!(
+            res,
+            Err(JsonPayloadError::Overflow { limit: 8, .. })
+        ));
+        assert_eq!(info.effective_limit(), 8);
+    }
+
+    /// Extract `Json<User>` failing, and respond with the error.
+    async fn reject(req: TestRequest) -> (JsonPayloadError, ntex::web::HttpResponse) {
+        let (req, mut payload) = req.to_http_parts();
+        match <Json<User> as FromRequest<DefaultError>>::from_request(&req, &mut payload).await {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => {
+                let res = e.error_response(&req);
+                (e, res)
+            }
+        }
+    }
+
+    #[ntex::test]
+    async fn test_unread_body() {
+        let config = JsonConfig::default().limit(4);
+        let req = request("application/json", r#"{"name":"axre"}"#).state(config);
+        let (e, res) = reject(req).await;
+        assert!(matches!(e, JsonPayloadError::Overflow { .. }));
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(res.head().connection_type(), ConnectionType::Close);
+
+        // the body is read to the end before it fails to parse
+        let req = request("application/json", r#"{"name":"#);
+        let (e, res) = reject(req).await;
+        assert!(matches!(e, JsonPayloadError::Syntax { .. }));
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_ne!(res.head().connection_type(), ConnectionType::Close);
+    }
+}
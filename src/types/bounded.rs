@@ -0,0 +1,242 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{parse_body, split_array, JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Json extractor for an array of models with bounded number of elements.
+///
+/// The body must be a json array of `MIN` to `MAX` elements, both inclusive, otherwise
+/// extraction fails with [`JsonPayloadError::TooFew`] or [`JsonPayloadError::TooMany`].
+/// The number of elements is checked before any element is validated, and a failing
+/// element is reported by [`JsonPayloadError::Element`] telling its 0-based index.
+/// The body as a whole is checked by [`JsonConfig`] options, e.g.
+/// [`JsonConfig::reject_duplicate_keys`], and [`JsonConfig::post_parse`] hook runs on
+/// each element.
+///
+/// `MIN` greater than `MAX` fails to compile once the extractor is used.
+///
+/// ```rust,ignore
+/// async fn bulk(users: BoundedJson<User, 1, 100>) -> String {
+///     format!("{} users", users.len())
+/// }
+/// ```
+pub struct BoundedJson<T, const MIN: usize, const MAX: usize>(pub Vec<T>);
+
+impl<T, const MIN: usize, const MAX: usize> BoundedJson<T, MIN, MAX> {
+    /// Evaluated by the extractor to reject inverted bounds at compile time.
+    const BOUNDS: () = assert!(MIN <= MAX, "MIN of BoundedJson is greater than MAX");
+
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T, const MIN: usize, const MAX: usize> ops::Deref for BoundedJson<T, MIN, MAX> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+
+impl<T, const MIN: usize, const MAX: usize> ops::DerefMut for BoundedJson<T, MIN, MAX> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.0
+    }
+}
+
+impl<T, Err: ErrorRenderer, const MIN: usize, const MAX: usize> FromRequest<Err>
+    for BoundedJson<T, MIN, MAX>
+where
+    T: Model + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::BOUNDS;
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) => config
+                    .check_body(&body)
+                    .and_then(|_| {
+                        config.parse_within(|| {
+                            parse_bounded(&body, config.capture_rejected, MIN, MAX)
+                        })
+                    })
+                    .and_then(|values| {
+                        values
+                            .into_iter()
+                            .map(|value| config.run_post_parse(&req, value))
+                            .collect()
+                    }),
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(BoundedJson)
+        })
+    }
+}
+
+/// Parse and validate bytes with the default config, checking the count of elements.
+///
+/// No request is at hand, so none of [`JsonConfig`] options apply, e.g. duplicate keys
+/// and trailing bytes are not checked and [`JsonConfig::post_parse`] hook does not run.
+impl<T: Model, const MIN: usize, const MAX: usize> TryFrom<&[u8]> for BoundedJson<T, MIN, MAX> {
+    type Error = JsonPayloadError;
+
+    fn try_from(body: &[u8]) -> Result<Self, Self::Error> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::BOUNDS;
+        parse_bounded(body, 0, MIN, MAX).map(BoundedJson)
+    }
+}
+
+fn parse_bounded<T: Model>(
+    body: &[u8],
+    capture: usize,
+    min: usize,
+    max: usize,
+) -> Result<Vec<T>, JsonPayloadError> {
+    if body.is_empty() {
+        return Err(JsonPayloadError::EmptyBody);
+    }
+    let elements = split_array(body, capture)?;
+    let len = elements.len();
+    if len < min {
+        return Err(JsonPayloadError::TooFew { min, len });
+    }
+    if len > max {
+        return Err(JsonPayloadError::TooMany { max, len });
+    }
+
+    elements
+        .into_iter()
+        .enumerate()
+        .map(|(index, element)| {
+            parse_body(element.get().as_bytes(), capture).map_err(|source| {
+                JsonPayloadError::Element {
+                    index,
+                    source: Box::new(source),
+                }
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use dade_derive::model;
+    use ntex::http::StatusCode;
+
+    #[model]
+    struct User {
+        #[field(min_length = 1, max_length = 10)]
+        name: String,
+    }
+
+    /// Json array of users with the names.
+    fn users(names: &[&str]) -> String {
+        let users: Vec<String> = names
+            .iter()
+            .map(|name| format!(r#"{{"name":"{}"}}"#, name))
+            .collect();
+        format!("[{}]", users.join(","))
+    }
+
+    #[test]
+    fn test_bounds() {
+        let parse = |names: &[&str]| BoundedJson::<User, 1, 3>::try_from(users(names).as_bytes());
+
+        assert!(matches!(
+            parse(&[]),
+            Err(JsonPayloadError::TooFew { min: 1, len: 0 })
+        ));
+        assert_eq!(parse(&["a"]).unwrap().len(), 1);
+        assert_eq!(parse(&["a", "b", "c"]).unwrap().len(), 3);
+        assert!(matches!(
+            parse(&["a", "b", "c", "d"]),
+            Err(JsonPayloadError::TooMany { max: 3, len: 4 })
+        ));
+    }
+
+    #[test]
+    fn test_exact_bounds() {
+        let parse = |names: &[&str]| BoundedJson::<User, 2, 2>::try_from(users(names).as_bytes());
+
+        assert!(matches!(
+            parse(&["a"]),
+            Err(JsonPayloadError::TooFew { min: 2, len: 1 })
+        ));
+        assert_eq!(parse(&["a", "b"]).unwrap().len(), 2);
+        assert!(matches!(
+            parse(&["a", "b", "c"]),
+            Err(JsonPayloadError::TooMany { max: 2, len: 3 })
+        ));
+    }
+
+    #[ntex::test]
+    async fn test_config_checks() {
+        let body = r#"[{"name":"a"},{"name":"b","name":"c"}]"#;
+        let config = JsonConfig::default().reject_duplicate_keys(true);
+        let req = request("application/json", body).state(config);
+        let res = extract::<BoundedJson<User, 1, 3>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::DuplicateKey(_))));
+
+        let config = JsonConfig::default().reject_trailing_bytes(true);
+        let req = request("application/json", r#"[{"name":"a"}] []"#).state(config);
+        let res = extract::<BoundedJson<User, 1, 3>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::Syntax { .. })));
+
+        let config =
+            JsonConfig::default().post_parse(|_, value| match value.downcast_ref::<User>() {
+                Some(user) if user.name == "b" => Err(JsonPayloadError::Rejected {
+                    status: StatusCode::CONFLICT,
+                    reason: "b is taken".to_string(),
+                }),
+                _ => Ok(()),
+            });
+        let req = request("application/json", users(&["a", "c"])).state(config.clone());
+        assert_eq!(
+            extract::<BoundedJson<User, 1, 3>>(req).await.unwrap().len(),
+            2
+        );
+        let req = request("application/json", users(&["a", "b"])).state(config);
+        let res = extract::<BoundedJson<User, 1, 3>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::Rejected { .. })));
+    }
+
+    #[ntex::test]
+    async fn test_element_index() {
+        let req = request("application/json", users(&["a", "", "c"]));
+        match extract::<BoundedJson<User, 1, 3>>(req).await {
+            Err(JsonPayloadError::Element { index, source }) => {
+                assert_eq!(index, 1);
+                assert!(matches!(*source, JsonPayloadError::Deserialize { .. }));
+            }
+            _ => panic!("expected an invalid element"),
+        }
+
+        // too many elements are reported before any element is validated
+        let req = request("application/json", users(&["", "", "", ""]));
+        let res = extract::<BoundedJson<User, 1, 3>>(req).await;
+        assert!(matches!(
+            res,
+            Err(JsonPayloadError::TooMany { max: 3, len: 4 })
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::errors::JsonPayloadError;
+use crate::types::body::{collect_body, content_length, mark_unread, read_within, take_payload};
+use crate::types::json::JsonConfig;
+use ntex::http::Payload;
+use ntex::util::Bytes;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Extractor of raw body bytes, limited by [`JsonConfig`] limit.
+///
+/// The body is neither checked for content type nor parsed, so an empty body is
+/// extracted as empty bytes, unless it is rejected by [`JsonConfig::allow_empty_body`].
+pub struct LimitedBytes(pub Bytes);
+
+impl LimitedBytes {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> Bytes {
+        self.0
+    }
+}
+
+impl ops::Deref for LimitedBytes {
+    type Target = Bytes;
+
+    fn deref(&self) -> &Bytes {
+        &self.0
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for LimitedBytes {
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+        let limit = config.limit;
+        if let Err(e) = config.check_method(req) {
+            mark_unread(req, &e);
+            return Box::pin(async move { Err(e) });
+        }
+
+        let len = content_length(req);
+        if len.map_or(false, |len| len > limit) {
+            let e = JsonPayloadError::Overflow { limit, length: len };
+            mark_unread(req, &e);
+            return Box::pin(async move { Err(e) });
+        }
+
+        let stream = take_payload(req, payload, &config);
+        let wire_limit = config.wire_limit;
+        let coalesce = config.coalesce;
+        let progress = config.progress();
+        let read_timeout = config.read_timeout;
+        let allow_empty = config.allow_empty_body.unwrap_or(true);
+        let req = req.clone();
+        Box::pin(async move {
+            let res = match stream {
+                Ok(stream) => {
+                    let fut =
+                        collect_body(stream, limit, len, wire_limit, coalesce, progress, None);
+                    read_within(fut, read_timeout).await
+                }
+                Err(e) => Err(e),
+            };
+            let res = match res {
+                Ok(body) if body.is_empty() && !allow_empty => Err(JsonPayloadError::EmptyBody),
+                res => res,
+            };
+            if let Err(ref e) = res {
+                mark_unread(&req, e);
+            }
+            res.map(|body| LimitedBytes(body.freeze()))
+        })
+    }
+}
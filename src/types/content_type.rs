@@ -0,0 +1,205 @@
+//! Composable predicates for allowed content types, and helpers for media type parameters.
+//!
+//! ```rust,ignore
+//! use axre::types::content_type::ContentTypePredicate as P;
+//!
+//! let predicate = P::any_of([P::exact(mime::TEXT_PLAIN), P::subtype("x-json")]);
+//! JsonConfig::default().content_type(predicate.into_fn());
+//! ```
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Media type served by a model, e.g. a versioned vendor media type.
+///
+/// Register it by
+/// [`JsonConfig::model_content_type`](crate::types::json::JsonConfig::model_content_type),
+/// then [`Json`](crate::types::Json) extractor for the model accepts the media type in
+/// addition to json content types, unless the config has its own content type predicate.
+///
+/// ```rust,ignore
+/// impl JsonContentType for User {
+///     const CONTENT_TYPE: &'static str = "application/vnd.user+json";
+/// }
+///
+/// JsonConfig::default().model_content_type::<User>();
+/// ```
+pub trait JsonContentType {
+    /// Media type accepted for the model, compared without parameters.
+    const CONTENT_TYPE: &'static str;
+}
+
+/// Get value of a parameter of media type, with surrounding quotes removed and quoted
+/// pairs unescaped, e.g. `utf-8` for `application/json; charset="utf-8"`.
+///
+/// Parameter names are compared case-insensitively.
+pub fn param(mime: &mime::Mime, name: &str) -> Option<String> {
+    mime.params()
+        .find(|(n, _)| n.as_str().eq_ignore_ascii_case(name))
+        .map(|(_, value)| unquote(value.as_str()))
+}
+
+/// Get all parameters of media type, keyed by lowercase name, with values unquoted
+/// same as [`param`], e.g. `{"charset": "utf-8", "profile": "urn:foo"}` for
+/// `application/json; charset=utf-8; profile="urn:foo"`.
+///
+/// When a parameter is repeated, the last value wins.
+pub fn params(mime: &mime::Mime) -> HashMap<String, String> {
+    mime.params()
+        .map(|(name, value)| (name.as_str().to_ascii_lowercase(), unquote(value.as_str())))
+        .collect()
+}
+
+/// Get version encoded in the subtype of media type, e.g. `3` for
+/// `application/vnd.api.v3+json`.
+///
+/// The version is the last dot-separated part of the subtype which is `v` followed by
+/// digits only, `None` if there is no such part.
+pub fn media_version(mime: &mime::Mime) -> Option<u32> {
+    let subtype = mime.subtype().as_str();
+    let subtype = subtype.split('+').next().unwrap_or(subtype);
+    subtype
+        .rsplit('.')
+        .filter_map(|part| part.strip_prefix('v').or_else(|| part.strip_prefix('V')))
+        .find(|version| !version.is_empty() && version.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|version| version.parse().ok())
+}
+
+/// Remove surrounding quotes of a parameter value and unescape quoted pairs.
+pub(crate) fn unquote(value: &str) -> String {
+    match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(quoted) => {
+            let mut value = String::with_capacity(quoted.len());
+            let mut chars = quoted.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    c => value.push(c),
+                }
+            }
+            value
+        }
+        None => value.to_string(),
+    }
+}
+
+/// Predicate for allowed content types, built with combinators.
+#[derive(Clone)]
+pub struct ContentTypePredicate(Arc<dyn Fn(&mime::Mime) -> bool + Send + Sync>);
+
+impl ContentTypePredicate {
+    /// Create predicate from a function.
+    pub fn from_fn<F>(f: F) -> Self
+    where
+        F: Fn(&mime::Mime) -> bool + Send + Sync + 'static,
+    {
+        ContentTypePredicate(Arc::new(f))
+    }
+
+    /// Match a media type with same type, subtype and suffix, parameters are ignored.
+    pub fn exact(mime: mime::Mime) -> Self {
+        Self::from_fn(move |m| m.essence_str().eq_ignore_ascii_case(mime.essence_str()))
+    }
+
+    /// Match a media type with the suffix, e.g. `json` for `application/vnd.api+json`.
+    pub fn suffix(suffix: &str) -> Self {
+        let suffix = suffix.to_string();
+        Self::from_fn(move |m| {
+            m.suffix()
+                .map_or(false, |s| s.as_str().eq_ignore_ascii_case(&suffix))
+        })
+    }
+
+    /// Match a media type with the subtype, e.g. `json` for `text/json`.
+    pub fn subtype(subtype: &str) -> Self {
+        let subtype = subtype.to_string();
+        Self::from_fn(move |m| m.subtype().as_str().eq_ignore_ascii_case(&subtype))
+    }
+
+    /// Match a media type when any of predicates matches.
+    pub fn any_of<I: IntoIterator<Item = ContentTypePredicate>>(predicates: I) -> Self {
+        let predicates: Vec<_> = predicates.into_iter().collect();
+        Self::from_fn(move |m| predicates.iter().any(|p| p.matches(m)))
+    }
+
+    /// Match a media type when all of predicates match.
+    pub fn all_of<I: IntoIterator<Item = ContentTypePredicate>>(predicates: I) -> Self {
+        let predicates: Vec<_> = predicates.into_iter().collect();
+        Self::from_fn(move |m| predicates.iter().all(|p| p.matches(m)))
+    }
+
+    /// Match a media type when this predicate does not match.
+    pub fn not(self) -> Self {
+        Self::from_fn(move |m| !self.matches(m))
+    }
+
+    /// Check whether the media type matches.
+    pub fn matches(&self, mime: &mime::Mime) -> bool {
+        (self.0)(mime)
+    }
+
+    /// Convert to a function for [`JsonConfig::content_type`](crate::types::json::JsonConfig::content_type).
+    pub fn into_fn(self) -> impl Fn(mime::Mime) -> bool + Send + Sync + 'static {
+        move |mime| self.matches(&mime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use crate::types::Json;
+    use dade_derive::model;
+
+    #[model]
+    struct User {
+        name: String,
+    }
+
+    #[test]
+    fn test_param() {
+        let mime: mime::Mime = r#"application/json; Charset="utf-8""#.parse().unwrap();
+        assert_eq!(param(&mime, "charset").as_deref(), Some("utf-8"));
+        assert_eq!(param(&mime, "profile"), None);
+
+        let mime: mime::Mime = "application/json; charset=utf-8".parse().unwrap();
+        assert_eq!(param(&mime, "CHARSET").as_deref(), Some("utf-8"));
+    }
+
+    #[test]
+    fn test_params() {
+        let mime: mime::Mime = r#"application/json; charset=utf-8; Profile="urn:foo""#
+            .parse()
+            .unwrap();
+        let params = params(&mime);
+        assert_eq!(params.len(), 2);
+        assert_eq!(params["charset"], "utf-8");
+        assert_eq!(params["profile"], "urn:foo");
+    }
+
+    #[test]
+    fn test_unquote() {
+        assert_eq!(unquote("utf-8"), "utf-8");
+        assert_eq!(unquote(r#""utf-8""#), "utf-8");
+        assert_eq!(unquote(r#""a \"b\" \\c""#), r#"a "b" \c"#);
+        assert_eq!(unquote(r#""""#), "");
+        // a lone quote is not a quoted string
+        assert_eq!(unquote(r#"""#), r#"""#);
+    }
+
+    #[ntex::test]
+    async fn test_quoted_charset() {
+        let req = request(r#"application/json; charset="utf-8""#, r#"{"name":"axre"}"#);
+        let user = extract::<Json<User>>(req).await.unwrap();
+        assert_eq!(user.name, "axre");
+
+        let req = request(
+            r#"application/vnd.api+json; charset="UTF-8""#,
+            r#"{"name":"axre"}"#,
+        );
+        let user = extract::<Json<User>>(req).await.unwrap();
+        assert_eq!(user.name, "axre");
+    }
+}
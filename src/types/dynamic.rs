@@ -0,0 +1,155 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{parse_body, syntax_error, JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::web::types::State;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops;
+use std::pin::Pin;
+use std::sync::Arc;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Validator of json bodies selected at runtime.
+///
+/// It is implemented for every model by [`DynSchema::of`], and can be implemented
+/// for validators loaded at runtime, e.g. by plugins.
+pub trait DynValidator: Send + Sync {
+    /// Validate the body, `capture` leading bytes of the body should be attached
+    /// to the error as [`JsonPayloadError::rejected_snippet`].
+    fn validate(&self, body: &[u8], capture: usize) -> Result<(), JsonPayloadError>;
+}
+
+struct ModelValidator<T>(PhantomData<fn() -> T>);
+
+impl<T: Model> DynValidator for ModelValidator<T> {
+    fn validate(&self, body: &[u8], capture: usize) -> Result<(), JsonPayloadError> {
+        parse_body::<T>(body, capture).map(|_| ())
+    }
+}
+
+/// Validator used by [`DynJson`], registered as app state of an app, a scope or a resource,
+/// either by `app_state` or by `state`.
+#[derive(Clone)]
+pub struct DynSchema(Arc<dyn DynValidator>);
+
+impl DynSchema {
+    /// Create schema validating bodies by the model `T`.
+    pub fn of<T: Model + 'static>() -> Self {
+        DynSchema(Arc::new(ModelValidator::<T>(PhantomData)))
+    }
+
+    /// Create schema from a validator.
+    pub fn new<V: DynValidator + 'static>(validator: V) -> Self {
+        DynSchema(Arc::new(validator))
+    }
+}
+
+/// Json extractor validating the body by [`DynSchema`] registered for the request.
+///
+/// This is a dynamic counterpart of [`Json`](crate::types::Json), the body is validated
+/// by the schema but resolves to `serde_json::Value`. Extraction fails with
+/// [`JsonPayloadError::MissingSchema`] when no schema is registered.
+pub struct DynJson(pub serde_json::Value);
+
+impl DynJson {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> serde_json::Value {
+        self.0
+    }
+}
+
+impl ops::Deref for DynJson {
+    type Target = serde_json::Value;
+
+    fn deref(&self) -> &serde_json::Value {
+        &self.0
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for DynJson {
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+        let schema = match req
+            .app_state::<DynSchema>()
+            .or_else(|| req.app_state::<State<DynSchema>>().map(|s| s.get_ref()))
+        {
+            Some(schema) => schema.clone(),
+            None => {
+                let req = req.clone();
+                return Box::pin(async move {
+                    let res = Err(JsonPayloadError::MissingSchema);
+                    config.complete(&req, &res).await;
+                    res
+                });
+            }
+        };
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) if body.is_empty() => Err(JsonPayloadError::EmptyBody),
+                Ok(body) => config
+                    .parse_within(|| schema.0.validate(&body, config.capture_rejected))
+                    .and_then(|_| {
+                        serde_json::from_slice(&body)
+                            .map_err(|source| syntax_error(&body, source, None))
+                    }),
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(DynJson)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use dade_derive::model;
+    use ntex::http::{ConnectionType, StatusCode};
+    use ntex::web::{DefaultError, WebResponseError};
+
+    #[model]
+    struct User {
+        #[field(min_length = 1, max_length = 10)]
+        name: String,
+    }
+
+    #[ntex::test]
+    async fn test_registered_schema() {
+        let req = request("application/json", r#"{"name":"axre"}"#).state(DynSchema::of::<User>());
+        let user = extract::<DynJson>(req).await.unwrap();
+        assert_eq!(user["name"], "axre");
+
+        let req =
+            request("application/json", r#"{"name":"axre"}"#).app_state(DynSchema::of::<User>());
+        let user = extract::<DynJson>(req).await.unwrap();
+        assert_eq!(user["name"], "axre");
+
+        let req = request("application/json", r#"{"name":""}"#).state(DynSchema::of::<User>());
+        let res = extract::<DynJson>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::Deserialize { .. })));
+    }
+
+    #[ntex::test]
+    async fn test_missing_schema() {
+        let (req, mut payload) = request("application/json", r#"{"name":"axre"}"#).to_http_parts();
+        let res = <DynJson as FromRequest<DefaultError>>::from_request(&req, &mut payload).await;
+        let e = match res {
+            Err(e @ JsonPayloadError::MissingSchema) => e,
+            _ => panic!("expected a missing schema"),
+        };
+        let res = e.error_response(&req);
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        // the body is left unread
+        assert_eq!(res.head().connection_type(), ConnectionType::Close);
+    }
+}
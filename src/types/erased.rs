@@ -0,0 +1,63 @@
+use crate::errors::JsonPayloadError;
+use ntex::http::Payload;
+use ntex::web::{DefaultError, FromRequest, HttpRequest};
+use std::any::Any;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+/// Future returned by [`BodyExtractor::extract`].
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// Object safe interface of extractors, for dispatching over extractors registered at
+/// runtime, e.g. `Vec<Box<dyn BodyExtractor>>`.
+///
+/// The value resolved by `extract` is the extractor itself, e.g. `Json<User>` for
+/// `Extractor<Json<User>>`, so it is downcast to the same type:
+///
+/// ```rust,ignore
+/// let extractor: Box<dyn BodyExtractor> = Box::new(Extractor::<Json<User>>::new());
+/// let value = extractor.extract(&req, &mut payload).await?;
+/// let user = value.downcast::<Json<User>>().unwrap();
+/// ```
+///
+/// Downcasting to another type fails, even to the model of `Json`.
+pub trait BodyExtractor {
+    /// Extract the body of request.
+    fn extract(
+        &self,
+        req: &HttpRequest,
+        payload: &mut Payload,
+    ) -> BoxFuture<Result<Box<dyn Any>, JsonPayloadError>>;
+}
+
+/// [`BodyExtractor`] for an extractor type `E` of this crate.
+pub struct Extractor<E>(PhantomData<fn() -> E>);
+
+impl<E> Extractor<E> {
+    /// Create extractor for the type `E`.
+    pub fn new() -> Self {
+        Extractor(PhantomData)
+    }
+}
+
+impl<E> Default for Extractor<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> BodyExtractor for Extractor<E>
+where
+    E: FromRequest<DefaultError, Error = JsonPayloadError> + 'static,
+    E::Future: 'static,
+{
+    fn extract(
+        &self,
+        req: &HttpRequest,
+        payload: &mut Payload,
+    ) -> BoxFuture<Result<Box<dyn Any>, JsonPayloadError>> {
+        let fut = E::from_request(req, payload);
+        Box::pin(async move { fut.await.map(|value| Box::new(value) as Box<dyn Any>) })
+    }
+}
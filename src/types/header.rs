@@ -0,0 +1,40 @@
+use ntex::http::header::{HeaderMap, HeaderName};
+
+/// Items of a header with quality values like `Accept`, with their quality, in order of
+/// the header. An item without `q` parameter has quality 1, and an item with
+/// quality 0 is an explicit refusal.
+pub(crate) fn parse_qualities(headers: &HeaderMap, name: HeaderName) -> Vec<(String, f32)> {
+    headers
+        .get_all(name)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|item| {
+            let mut parts = item.split(';').map(|s| s.trim());
+            let value = parts.next().unwrap_or("").to_string();
+            let quality = parts
+                .filter_map(|param| param.strip_prefix("q="))
+                .filter_map(|q| q.parse::<f32>().ok())
+                .next()
+                .unwrap_or(1.0);
+            match value.is_empty() {
+                true => None,
+                false => Some((value, quality)),
+            }
+        })
+        .collect()
+}
+
+/// Quality of `value` by items of [`parse_qualities`], `None` if it is not listed.
+///
+/// An item of the value itself takes precedence over wildcards, so `gzip;q=0, *` refuses
+/// gzip. Otherwise the quality is of the first of `wildcards` listed, which are given from
+/// the most specific, e.g. `application/*` before `*/*`.
+pub(crate) fn quality(qualities: &[(String, f32)], value: &str, wildcards: &[&str]) -> Option<f32> {
+    let of = |value: &str| {
+        qualities
+            .iter()
+            .find(|(item, _)| item.eq_ignore_ascii_case(value))
+            .map(|(_, quality)| *quality)
+    };
+    of(value).or_else(|| wildcards.iter().find_map(|wildcard| of(wildcard)))
+}
@@ -1,25 +1,211 @@
-use crate::errors::JsonPayloadError;
+use crate::errors::{JsonErrorRender, JsonPayloadError, LocaleMessages, RejectedModel, RenderFn};
+#[cfg(feature = "metrics")]
+use crate::metrics::{BodySizeSink, JsonMetrics, SizeRecorder};
+use crate::shutdown::ShutdownSignal;
+use crate::types::body::{
+    collect_body, content_length, has_body, mark_unread, read_within, take_payload, BodyStream,
+    DecoderFn, InspectFn,
+};
+use crate::types::content_type::JsonContentType;
+use crate::types::header::{parse_qualities, quality};
 use dade::Model;
-use ntex::http::{HttpMessage, Payload};
-use ntex::util::{stream_recv, BytesMut};
-use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+#[cfg(feature = "compress")]
+use ntex::http::body::{Body, ResponseBody};
+#[cfg(feature = "compress")]
+use ntex::http::encoding::Encoder;
+#[cfg(feature = "compress")]
+use ntex::http::header::{ContentEncoding, ACCEPT_ENCODING};
+use ntex::http::header::{ACCEPT, ACCEPT_CHARSET, CONTENT_TYPE, ETAG, IF_NONE_MATCH, VARY};
+use ntex::http::{HttpMessage, Method, Payload, StatusCode};
+use ntex::time::{timeout, Millis};
+use ntex::util::{Bytes, BytesMut};
+use ntex::web::types::State;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest, HttpResponse, Responder};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
 use std::future::Future;
 use std::ops;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 type PinBox<T> = Pin<Box<T>>;
 
+/// Predicate for allowed content types, see [`JsonConfig::content_type_with_request`].
+pub(crate) type ContentTypeFn = Arc<dyn Fn(&HttpRequest, mime::Mime) -> bool + Send + Sync>;
+
+/// Hook run before reading body, see [`JsonConfig::pre_parse`].
+pub(crate) type PreParseFn =
+    Arc<dyn Fn(&HttpRequest) -> Result<(), JsonPayloadError> + Send + Sync>;
+
+/// Hook run on parsed value, see [`JsonConfig::post_parse`].
+pub(crate) type PostParseFn =
+    Arc<dyn Fn(&HttpRequest, &dyn Any) -> Result<(), JsonPayloadError> + Send + Sync>;
+
+/// Hook rewriting buffered body, see [`JsonConfig::rewrite_body`].
+pub(crate) type RewriteFn = Arc<dyn Fn(&mut BytesMut) + Send + Sync>;
+
+/// Hook run on progress of reading body, see [`JsonConfig::on_progress`].
+pub(crate) type ProgressFn = Arc<dyn Fn(usize) + Send + Sync>;
+
+/// Hook run on rejected bodies, see [`JsonConfig::on_reject`].
+pub(crate) type RejectFn =
+    Arc<dyn Fn(&JsonPayloadError, &HttpRequest) -> PinBox<dyn Future<Output = ()>> + Send + Sync>;
+
+static DEFAULT_LIMIT: AtomicUsize = AtomicUsize::new(32768);
+
+/// Set the process-wide default max size of payload, used when no [`JsonConfig`]
+/// is registered as app state. By default max size is 32Kb.
+///
+/// It is also the limit of a config created by `JsonConfig::default()`, unless it is
+/// changed by [`JsonConfig::limit`]. A config reads the default when it is created, so
+/// this should be called before configs are created, e.g. at the start of `main`.
+///
+/// The value is stored in an atomic, so it is safe to call from any thread, but it is
+/// meant to be set once at startup before the server begins handling requests.
+/// A registered `JsonConfig` with an explicit limit always takes precedence over this
+/// default.
+pub fn set_default_json_limit(limit: usize) {
+    DEFAULT_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+/// Get the process-wide default max size of payload.
+pub fn default_json_limit() -> usize {
+    DEFAULT_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Json extractor and responder for models.
+///
+/// Any `*/json` or `*/*+json` content type is json, e.g. `text/json`, and with
+/// `lenient-content-type` feature `text/plain` is too. Other content types are accepted
+/// by [`JsonConfig::content_type`].
+///
+/// Whether the body was compressed is available from [`BodyInfo`](crate::types::BodyInfo)
+/// after extraction.
 pub struct Json<T>(pub T);
 
 impl<T> Json<T> {
+    /// Create `Json` from a value, e.g. to respond with it.
+    ///
+    /// ```rust,ignore
+    /// async fn index() -> Json<User> {
+    ///     Json::new(User { name: "axre".to_string() })
+    /// }
+    /// ```
+    pub fn new(value: T) -> Self {
+        Json(value)
+    }
+
     /// Deconstruct to an inner value
     pub fn into_inner(self) -> T {
         self.0
     }
 }
 
+/// ```rust,ignore
+/// async fn index() -> Json<User> {
+///     User { name: "axre".to_string() }.into()
+/// }
+/// ```
+impl<T> From<T> for Json<T> {
+    fn from(value: T) -> Self {
+        Json(value)
+    }
+}
+
+impl<T: Model> Json<T> {
+    /// Parse and validate bytes with the default config, e.g. a message from a queue.
+    ///
+    /// No request is at hand, so none of [`JsonConfig`] options apply, e.g. duplicate
+    /// keys and trailing bytes are not checked and [`JsonConfig::post_parse`] hook does
+    /// not run.
+    ///
+    /// `Json` does not implement `TryFrom<&[u8]>`, since it would conflict with
+    /// `From<T>` for `Json<&[u8]>`, the other wrappers of bodies implement it instead.
+    pub fn from_slice(body: &[u8]) -> Result<Json<T>, JsonPayloadError> {
+        if body.is_empty() {
+            return Err(JsonPayloadError::EmptyBody);
+        }
+        parse_body::<T>(body, 0).map(Json)
+    }
+
+    /// Validate the inner value again, e.g. after it was mutated by handler.
+    ///
+    /// The value is serialized and parsed through the model, so this costs as much as
+    /// the extraction itself.
+    pub fn revalidate(&self) -> Result<(), JsonPayloadError> {
+        parse_body::<T>(self.0.json(false).as_bytes(), 0).map(|_| ())
+    }
+
+    /// Mutate the inner value by `f` and validate it again, leaving the value unchanged
+    /// if the result is invalid.
+    ///
+    /// `DerefMut` and the public field give unchecked access, so a handler may break
+    /// invariants of the model validated by the extractor. For models where this matters,
+    /// mutate them through `modify` only. `f` mutates a copy of the value made by
+    /// serializing and parsing it, which replaces the value once it is validated again,
+    /// so this costs about twice as much as [`revalidate`](Json::revalidate).
+    ///
+    /// ```rust,ignore
+    /// user.modify(|user| user.age += 1)?;
+    /// ```
+    pub fn modify<F, R>(&mut self, f: F) -> Result<R, JsonPayloadError>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut copy = parse_body::<T>(self.0.json(false).as_bytes(), 0)?;
+        let res = f(&mut copy);
+        self.0 = parse_body::<T>(copy.json(false).as_bytes(), 0)?;
+        Ok(res)
+    }
+
+    /// Parse and validate bytes same as [`Json::from_slice`], but never panic.
+    ///
+    /// A panic of the parser is caught and returned as [`JsonPayloadError::Panic`].
+    /// This is the entry point for fuzzing, see `fuzz` directory of the repository.
+    pub fn parse_fuzz(data: &[u8]) -> Result<Json<T>, JsonPayloadError> {
+        std::panic::catch_unwind(|| Json::from_slice(data)).unwrap_or(Err(JsonPayloadError::Panic))
+    }
+
+    /// Extract and validate the body same as the extractor, and pass a reference to the
+    /// value to `f` within the request handler.
+    ///
+    /// The value is borrowed by `f` only, so a result borrowing from values of the
+    /// handler can be built from it without moving the value out. The body is checked by
+    /// the same options of [`JsonConfig`] as the extractor, including the options of the
+    /// model like [`JsonConfig::model_content_type`], and [`JsonConfig::post_parse`] hook
+    /// runs before `f`. The model must be `'static`, since those options are looked up
+    /// by its type.
+    ///
+    /// ```rust,ignore
+    /// async fn index(req: HttpRequest, mut payload: Payload) -> Result<String, JsonPayloadError> {
+    ///     Json::<User>::scoped(&req, &mut payload, |user| format!("Welcome {}!", user.name)).await
+    /// }
+    /// ```
+    pub async fn scoped<F, R>(
+        req: &HttpRequest,
+        payload: &mut Payload,
+        f: F,
+    ) -> Result<R, JsonPayloadError>
+    where
+        T: 'static,
+        F: FnOnce(&T) -> R,
+    {
+        let config = JsonConfig::from_req(req).for_model::<T>();
+        let fut = JsonBody::new(req, payload, &config).limit(config.limit);
+
+        let res = match fut.await {
+            Ok(body) => config.parse(req, body).await,
+            Err(e) => Err(e),
+        };
+        config.complete(req, &res).await;
+        res.map(|value| f(&value))
+    }
+}
+
 impl<T> ops::Deref for Json<T> {
     type Target = T;
 
@@ -28,12 +214,40 @@ impl<T> ops::Deref for Json<T> {
     }
 }
 
+/// Mutation through `DerefMut` is not validated, see [`Json::modify`].
 impl<T> ops::DerefMut for Json<T> {
     fn deref_mut(&mut self) -> &mut T {
         &mut self.0
     }
 }
 
+impl<T: fmt::Debug> fmt::Debug for Json<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Json").field(&self.0).finish()
+    }
+}
+
+impl<T: Clone> Clone for Json<T> {
+    fn clone(&self) -> Self {
+        Json(self.0.clone())
+    }
+}
+
+impl<T: PartialEq> PartialEq for Json<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+/// Extract and validate the body of request.
+///
+/// The future is not `Send`, same as futures of all extractors of this crate and of ntex
+/// itself. It holds the `HttpRequest`, which is reference counted without atomics, since
+/// ntex runs each worker on a single thread and never moves a request between threads.
+/// So handlers compose with any number of workers, and a `Send` bound can not be added.
+/// The model does not need to be `Send` either, the body is always parsed on the current
+/// thread. [`BlockingJson`](crate::types::BlockingJson) parses on the blocking thread pool
+/// instead, for `Send` models.
 impl<T, Err: ErrorRenderer> FromRequest<Err> for Json<T>
 where
     T: Model + 'static,
@@ -42,113 +256,1069 @@ where
     type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
 
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        let (limit, ctype) = req
-            .app_state::<JsonConfig>()
-            .map(|c| (c.limit, c.content_type.clone()))
-            .unwrap_or((32768, None));
+        let config = JsonConfig::from_req(req).for_model::<T>();
 
-        let fut = JsonBody::new(req, payload, ctype).limit(limit);
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
         Box::pin(async move {
-            match fut.await {
+            let res = match fut.await {
+                Ok(body) => config.parse(&req, body).await,
                 Err(e) => Err(e),
-                Ok(data) => Ok(Json(data)),
+            };
+            if res.is_err() && config.renderers.contains_key(&TypeId::of::<T>()) {
+                req.extensions_mut()
+                    .insert(RejectedModel(TypeId::of::<T>()));
             }
+            config.complete(&req, &res).await;
+            res.map(Json)
         })
     }
 }
 
+/// Serialize the inner value to a `application/json; charset=utf-8` response, the charset
+/// can be omitted by [`JsonConfig::response_charset`].
+///
+/// When the request has `Accept-Charset` header which does not accept utf-8, responds
+/// with `406 Not Acceptable`.
+///
+/// With [`JsonConfig::etag`], responses have a weak `ETag`, and `GET` or `HEAD` requests
+/// with a matching `If-None-Match` get `304 Not Modified`.
+///
+/// With `compress` feature, bodies larger than [`JsonConfig::compress_above`] are
+/// compressed by gzip, deflate or brotli when the request accepts it by
+/// `Accept-Encoding`. Responses have `Vary: accept-encoding` whether compressed or not.
+impl<T: Model, Err: ErrorRenderer> Responder<Err> for Json<T> {
+    type Future = std::future::Ready<HttpResponse>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        std::future::ready(respond_json(req, || self.0.json(false)))
+    }
+}
+
+/// Create response of json body serialized by `serialize`, see [`Json`] responder.
+pub(crate) fn respond_json<F>(req: &HttpRequest, serialize: F) -> HttpResponse
+where
+    F: FnOnce() -> String,
+{
+    if accepts_utf8(req) == Some(false) {
+        return HttpResponse::build(StatusCode::NOT_ACCEPTABLE).finish();
+    }
+
+    let config = JsonConfig::from_req(req);
+    if config.strict_accept && accepts_json(req) == Some(false) {
+        return HttpResponse::build(StatusCode::NOT_ACCEPTABLE).finish();
+    }
+    let content_type = if config.response_charset {
+        "application/json; charset=utf-8"
+    } else {
+        "application/json"
+    };
+
+    let mut builder = HttpResponse::build(StatusCode::OK);
+    builder.header(CONTENT_TYPE, content_type);
+    // the body depends on `Accept-Encoding` whenever compression is enabled, so caches
+    // must not serve it to other clients even when this one gets it uncompressed
+    let negotiated = cfg!(feature = "compress") && config.compress_above != usize::MAX;
+    if negotiated {
+        builder.header(VARY, "accept-encoding");
+    }
+    let body = serialize();
+
+    if config.etag {
+        let etag = weak_etag(body.as_bytes());
+        if matches!(*req.method(), Method::GET | Method::HEAD) && none_match(req, &etag) {
+            let mut not_modified = HttpResponse::build(StatusCode::NOT_MODIFIED);
+            if negotiated {
+                not_modified.header(VARY, "accept-encoding");
+            }
+            return not_modified.header(ETAG, etag).finish();
+        }
+        builder.header(ETAG, etag);
+    }
+
+    #[cfg(feature = "compress")]
+    {
+        if negotiated && body.len() > config.compress_above {
+            let encoding = response_encoding(req);
+            if encoding != ContentEncoding::Identity {
+                return builder.body(body).map_body(|head, body| {
+                    ResponseBody::Other(Body::from_message(Encoder::response(encoding, head, body)))
+                });
+            }
+        }
+    }
+
+    builder.body(body)
+}
+
+/// Weak entity tag of serialized body, by a hash stable for a build of the crate.
+fn weak_etag(body: &[u8]) -> String {
+    use std::hash::Hasher;
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    hasher.write(body);
+    format!("W/\"{:016x}\"", hasher.finish())
+}
+
+/// Check whether `If-None-Match` header of request matches the entity tag, compared
+/// weakly as required for `If-None-Match`.
+fn none_match(req: &HttpRequest, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    req.headers()
+        .get_all(IF_NONE_MATCH)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+/// Resolve the most preferred encoding of response accepted by `Accept-Encoding` header,
+/// `Identity` if none of gzip, deflate and brotli is accepted.
+#[cfg(feature = "compress")]
+fn response_encoding(req: &HttpRequest) -> ContentEncoding {
+    let qualities = parse_qualities(req.headers(), ACCEPT_ENCODING);
+    let mut accepted: Vec<(ContentEncoding, f32)> = [
+        ContentEncoding::Gzip,
+        ContentEncoding::Br,
+        ContentEncoding::Deflate,
+    ]
+    .into_iter()
+    .filter_map(|encoding| Some((encoding, quality(&qualities, encoding.as_str(), &["*"])?)))
+    .filter(|(_, quality)| *quality > 0.0)
+    .collect();
+    // stable sort prefers gzip, then brotli, for encodings of same quality
+    accepted.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    accepted
+        .first()
+        .map_or(ContentEncoding::Identity, |(encoding, _)| *encoding)
+}
+
+/// Check whether `Accept-Charset` header of request accepts utf-8,
+/// `None` if there is no such header.
+fn accepts_utf8(req: &HttpRequest) -> Option<bool> {
+    if !req.headers().contains_key(ACCEPT_CHARSET) {
+        return None;
+    }
+    let qualities = parse_qualities(req.headers(), ACCEPT_CHARSET);
+    Some(quality(&qualities, "utf-8", &["*"]).map_or(false, |quality| quality > 0.0))
+}
+
+/// Check whether `Accept` header of request accepts `application/json`, e.g. by
+/// `application/*` or `*/*`, `None` when the header is missing.
+fn accepts_json(req: &HttpRequest) -> Option<bool> {
+    if !req.headers().contains_key(ACCEPT) {
+        return None;
+    }
+    let qualities = parse_qualities(req.headers(), ACCEPT);
+    let quality = quality(&qualities, "application/json", &["application/*", "*/*"]);
+    Some(quality.map_or(false, |quality| quality > 0.0))
+}
+
+/// Json extractor configuration.
+///
+/// Register it with `app_state` of the app, a scope or a resource. A config registered
+/// with `state` is found too, it is looked up after configs registered with `app_state`.
+/// The extractors read it with `HttpRequest::app_state`, which looks up the most specific
+/// registration first, so a config of a scope overrides the config of the app for requests
+/// in the scope:
+///
+/// ```rust,ignore
+/// App::new()
+///     .app_state(JsonConfig::default().limit(4096))
+///     .service(web::scope("/admin").app_state(JsonConfig::default().limit(1_048_576)))
+/// ```
+///
+/// The most specific config replaces others as a whole, settings are not merged.
+/// Without any config the [default limit](crate::set_default_json_limit) is used.
+///
+/// A config can be set for a single request too, by inserting it into the extensions of
+/// the request, e.g. by a middleware wrapping a route. So the config is resolved in order:
+///
+/// 1. config in the extensions of request,
+/// 2. config registered with `app_state`, of the resource, the scope, then the app,
+/// 3. config registered with `state`,
+/// 4. the default config with the default limit.
+///
+/// The two ways of registration are looked up one after another, not by specificity:
+/// any config registered with `app_state`, even the one of the app, wins over a config
+/// registered with `state` on a resource. So register all configs of an app the same
+/// way, preferably with `app_state`, otherwise the precedence is inverted.
+///
+/// ntex has no `route_data`, route specific state is registered with `app_state` of the
+/// resource instead:
+///
+/// ```rust,ignore
+/// web::resource("/upload")
+///     .app_state(JsonConfig::default().limit_mb(8))
+///     .route(web::post().to(upload))
+/// ```
 #[derive(Clone)]
 pub struct JsonConfig {
-    limit: usize,
-    content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    pub(crate) limit: usize,
+    pub(crate) content_type: Option<ContentTypeFn>,
+    pub(crate) capture_rejected: usize,
+    pub(crate) validation_status: StatusCode,
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics: Option<Arc<JsonMetrics>>,
+    #[cfg(feature = "metrics")]
+    pub(crate) size_sink: Option<Arc<dyn BodySizeSink>>,
+    pub(crate) on_reject: Option<RejectFn>,
+    pub(crate) decoders: Arc<HashMap<String, DecoderFn>>,
+    pub(crate) application_wildcard: bool,
+    pub(crate) wire_limit: Option<usize>,
+    pub(crate) coalesce: usize,
+    pub(crate) compress_above: usize,
+    pub(crate) decode_at_once_below: usize,
+    pub(crate) reject_duplicate_keys: bool,
+    pub(crate) reject_trailing_bytes: bool,
+    pub(crate) limit_set: bool,
+    pub(crate) limit_from_schema: bool,
+    pub(crate) on_progress: Option<ProgressFn>,
+    pub(crate) forbid_body_on: Vec<Method>,
+    pub(crate) response_charset: bool,
+    pub(crate) strict_accept: bool,
+    pub(crate) json_part: String,
+    pub(crate) json_errors: bool,
+    pub(crate) locale_messages: Option<Arc<dyn LocaleMessages>>,
+    pub(crate) skip_content_type_check: bool,
+    pub(crate) allow_empty_body: Option<bool>,
+    pub(crate) preview: usize,
+    pub(crate) base64_body: bool,
+    pub(crate) etag: bool,
+    pub(crate) coerce_numeric_strings: bool,
+    pub(crate) model_content_types: Arc<HashMap<TypeId, &'static str>>,
+    pub(crate) renderers: Arc<HashMap<TypeId, RenderFn>>,
+    pub(crate) pre_parse: Option<PreParseFn>,
+    pub(crate) post_parse: Option<PostParseFn>,
+    pub(crate) rewrite_body: Option<RewriteFn>,
+    pub(crate) shutdown: Option<ShutdownSignal>,
+    pub(crate) read_timeout: Option<Millis>,
+    pub(crate) parse_timeout: Option<Duration>,
+    pub(crate) alloc_budget: Option<usize>,
 }
 
 impl JsonConfig {
-    /// Change max size of payload. By default max size is 32Kb
+    /// Get config of the request, see [`JsonConfig`] for the resolution order. A config
+    /// registered with `app_state` at any level wins over one registered with `state`.
+    pub(crate) fn from_req(req: &HttpRequest) -> Self {
+        if let Some(config) = req.extensions().get::<JsonConfig>() {
+            return config.clone();
+        }
+        req.app_state::<JsonConfig>()
+            .or_else(|| req.app_state::<State<JsonConfig>>().map(|s| s.get_ref()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Change max size of payload. By default max size is the
+    /// [default limit](crate::set_default_json_limit), 32Kb unless it is changed.
+    ///
+    /// Bodies over the limit are always rejected with [`JsonPayloadError::Overflow`], they
+    /// are never truncated, as a truncated json document can not be parsed anyway.
+    /// So responses never need to report the number of bytes processed.
+    ///
+    /// A declared `Content-Length` over the limit is rejected before reading the body.
+    /// For requests with `Expect: 100-continue` this responds `413 Payload Too Large` as
+    /// the final status, though ntex answers the expectation itself before extractors
+    /// run, so the client may have started to send the body already.
     pub fn limit(mut self, limit: usize) -> Self {
         self.limit = limit;
+        self.limit_set = true;
+        self
+    }
+
+    /// Derive max size of payload of [`Json`] extractor from the schema of its model,
+    /// unless a limit is set explicitly by [`JsonConfig::limit`]. Disabled by default.
+    ///
+    /// The size is estimated as the largest body of the model, by `maxLength` of strings,
+    /// `maxItems` of arrays and fields of objects, then doubled to allow for whitespace.
+    /// It is a heuristic, e.g. unknown fields or escapes may not fit the estimate, so
+    /// an explicit limit should be set for a model it does not suit. A model with any
+    /// unbounded string or array keeps the limit as is. The derived limit only ever
+    /// lowers the limit, and is at least 1Kb.
+    ///
+    /// ```rust,ignore
+    /// // a model of short strings only accepts a body of a few Kb
+    /// web::resource("/tags").app_state(JsonConfig::default().limit_from_schema(true))
+    /// ```
+    pub fn limit_from_schema(mut self, enabled: bool) -> Self {
+        self.limit_from_schema = enabled;
+        self
+    }
+
+    /// Change max size of payload in kibibytes, e.g. `limit_kb(64)` for 64 * 1024 bytes.
+    pub fn limit_kb(self, limit: usize) -> Self {
+        self.limit(limit.saturating_mul(1024))
+    }
+
+    /// Change max size of payload in mebibytes, e.g. `limit_mb(8)` for 8 * 1024 * 1024
+    /// bytes. Use [`JsonConfig::limit_kb`] for a fraction of a mebibyte.
+    pub fn limit_mb(self, limit: usize) -> Self {
+        self.limit(limit.saturating_mul(1024 * 1024))
+    }
+
+    /// Change max size of payload read from the wire, before it is decoded according to
+    /// `Content-Encoding`. By default only the decoded size is limited by
+    /// [`JsonConfig::limit`].
+    ///
+    /// Both limits are enforced independently, exceeding either one fails with
+    /// [`JsonPayloadError::Overflow`].
+    pub fn wire_limit(mut self, limit: usize) -> Self {
+        self.wire_limit = Some(limit);
+        self
+    }
+
+    /// Coalesce chunks of body until at least `size` bytes are pending or the body ends,
+    /// before appending them to the buffer at once. This reduces reallocations of the
+    /// buffer for bodies received in many tiny chunks, see `benches/read.rs`.
+    ///
+    /// By default chunks are appended as they arrive.
+    pub fn coalesce(mut self, size: usize) -> Self {
+        self.coalesce = size;
+        self
+    }
+
+    /// Accept strings of numbers, e.g. `"age":"30"`, for fields of number types. By default
+    /// the model rejects them.
+    ///
+    /// Models have no coercion, so the body is parsed once more to replace the strings
+    /// according to json schema of the model. Only top level fields of an object are
+    /// coerced, fields of nested objects are not.
+    pub fn coerce_numeric_strings(mut self, enabled: bool) -> Self {
+        self.coerce_numeric_strings = enabled;
+        self
+    }
+
+    /// Reject bodies with duplicate keys in an object with
+    /// [`JsonPayloadError::DuplicateKey`]. By default the last value of a duplicate key
+    /// wins silently.
+    ///
+    /// This costs one more pass over the body before it is parsed.
+    pub fn reject_duplicate_keys(mut self, enabled: bool) -> Self {
+        self.reject_duplicate_keys = enabled;
+        self
+    }
+
+    /// Reject bodies with anything but whitespace after the json value, e.g.
+    /// `{"name":"axre"} garbage`, with [`JsonPayloadError::Syntax`] at the first trailing
+    /// byte. By default the model decides, which may ignore trailing bytes, so this is
+    /// off to keep bodies accepted today accepted.
+    ///
+    /// This costs one more pass over the body before it is parsed.
+    pub fn reject_trailing_bytes(mut self, enabled: bool) -> Self {
+        self.reject_trailing_bytes = enabled;
+        self
+    }
+
+    /// Respond errors with json documents of [`JsonPayloadError::problem`] instead of plain
+    /// text, e.g. `{"error":"payload_too_large","limit":262144,"request_id":"abc"}`.
+    ///
+    /// The documents and `X-Request-Id` header of responses echo the correlation id of
+    /// request, see [`request_id`](crate::errors::request_id).
+    pub fn json_errors(mut self, enabled: bool) -> Self {
+        self.json_errors = enabled;
+        self
+    }
+
+    /// Set localized messages of errors, chosen by `Accept-Language` of request, see
+    /// [`LocaleMessages`]. By default messages are in english.
+    pub fn locale_messages<L: LocaleMessages + 'static>(mut self, messages: L) -> Self {
+        self.locale_messages = Some(Arc::new(messages));
+        self
+    }
+
+    /// Set weak `ETag` on [`Json`] responses, computed from the serialized body, and
+    /// respond `304 Not Modified` to `GET` and `HEAD` requests with a matching
+    /// `If-None-Match`. Disabled by default.
+    pub fn etag(mut self, enabled: bool) -> Self {
+        self.etag = enabled;
+        self
+    }
+
+    /// Whether [`Json`] responses have `charset=utf-8` parameter in content type. By
+    /// default it is present, disable it for clients which do not expect parameters.
+    pub fn response_charset(mut self, enabled: bool) -> Self {
+        self.response_charset = enabled;
+        self
+    }
+
+    /// Change name of the part of multipart body extracted by
+    /// [`JsonPart`](crate::types::JsonPart). By default the part is `metadata`.
+    pub fn json_part(mut self, name: &str) -> Self {
+        self.json_part = name.to_string();
+        self
+    }
+
+    /// Respond `406 Not Acceptable` from [`Json`] responder when `Accept` header of request
+    /// does not accept `application/json`, e.g. `Accept: application/xml`. By default json
+    /// is sent regardless of `Accept`, a request without the header accepts json either way.
+    pub fn strict_accept(mut self, enabled: bool) -> Self {
+        self.strict_accept = enabled;
+        self
+    }
+
+    /// Change size of [`Json`] responses above which they are compressed, with `compress`
+    /// feature. By default responses larger than 1Kb are compressed, `usize::MAX`
+    /// disables compression.
+    ///
+    /// Responses are compressed by gzip, deflate or brotli, whichever `Accept-Encoding`
+    /// prefers, gzip first for encodings of same quality. Unless compression is disabled,
+    /// all responses have `Vary: accept-encoding`, since whether they are compressed
+    /// depends on the header.
+    pub fn compress_above(mut self, size: usize) -> Self {
+        self.compress_above = size;
         self
     }
 
-    /// Set predicate for allowed content types
+    /// Change `Content-Length` of gzip and deflate bodies below which they are read
+    /// compressed and decoded at once, with `compress` feature. Larger bodies and bodies
+    /// without `Content-Length` are decoded as a stream while reading. By default bodies
+    /// smaller than 16Kb are decoded at once, `0` always decodes as a stream.
+    pub fn decode_at_once_below(mut self, size: usize) -> Self {
+        self.decode_at_once_below = size;
+        self
+    }
+
+    /// Set predicate for allowed content types, in addition to json content types.
+    ///
+    /// The predicate receives the full media type including parameters, which can be read
+    /// by [`param`](crate::types::content_type::param) or
+    /// [`params`](crate::types::content_type::params):
+    ///
+    /// ```rust,ignore
+    /// use axre::types::content_type::param;
+    ///
+    /// JsonConfig::default().content_type(|mime| {
+    ///     mime.essence_str() == "application/ld+json"
+    ///         && param(&mime, "profile").as_deref() == Some("urn:foo")
+    /// })
+    /// ```
+    ///
+    /// Complex predicates can be built with
+    /// [`ContentTypePredicate`](crate::types::content_type::ContentTypePredicate).
     pub fn content_type<F>(mut self, predicate: F) -> Self
     where
         F: Fn(mime::Mime) -> bool + Send + Sync + 'static,
+    {
+        self.content_type = Some(Arc::new(move |_: &HttpRequest, mime| predicate(mime)));
+        self
+    }
+
+    /// Set predicate for allowed content types, which also receives the request.
+    ///
+    /// This allows to accept content types depending on the request, e.g. on the method:
+    ///
+    /// ```rust,ignore
+    /// JsonConfig::default().content_type_with_request(|req, mime| {
+    ///     req.method() == Method::PUT && mime == mime::APPLICATION_WWW_FORM_URLENCODED
+    /// })
+    /// ```
+    ///
+    /// Replaces a predicate set by [`JsonConfig::content_type`].
+    pub fn content_type_with_request<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&HttpRequest, mime::Mime) -> bool + Send + Sync + 'static,
     {
         self.content_type = Some(Arc::new(predicate));
         self
     }
+
+    /// Change max number of bytes of body previewed by
+    /// [`JsonWithPreview`](crate::types::JsonWithPreview). By default 256 bytes.
+    pub fn preview(mut self, len: usize) -> Self {
+        self.preview = len;
+        self
+    }
+
+    /// Accept `text/plain` bodies of base64 encoded json, they are decoded before parsing
+    /// and an invalid encoding fails with [`JsonPayloadError::Base64`]. Bodies of json
+    /// content types are parsed as is.
+    ///
+    /// The limit applies to the encoded body.
+    pub fn base64_body(mut self, enabled: bool) -> Self {
+        self.base64_body = enabled;
+        self
+    }
+
+    /// Skip checking content type, any request is read as json, even without content type.
+    ///
+    /// Meant for apps behind a gateway which already checked content types. By default
+    /// the content type is checked.
+    pub fn skip_content_type_check(mut self, enabled: bool) -> Self {
+        self.skip_content_type_check = enabled;
+        self
+    }
+
+    /// Whether extractors without a model accept an empty body, [`LimitedBytes`] as empty
+    /// bytes and [`JsonValue`] as `null`. Otherwise they fail with
+    /// [`JsonPayloadError::EmptyBody`]. By default `LimitedBytes` accepts it and
+    /// `JsonValue` does not.
+    ///
+    /// Extractors of models always reject an empty body, use
+    /// [`OptionalJson`](crate::types::OptionalJson) or
+    /// [`JsonOrDefault`](crate::types::JsonOrDefault) to accept it.
+    ///
+    /// [`LimitedBytes`]: crate::types::LimitedBytes
+    /// [`JsonValue`]: crate::types::JsonValue
+    pub fn allow_empty_body(mut self, allow: bool) -> Self {
+        self.allow_empty_body = Some(allow);
+        self
+    }
+
+    /// Accept any `application/*` content type as json, as a last resort for clients or
+    /// proxies sending odd content types. By default only json content types are accepted.
+    pub fn accept_application_wildcard(mut self, enabled: bool) -> Self {
+        self.application_wildcard = enabled;
+        self
+    }
+
+    /// Attach up to `size` leading bytes of a body which failed to deserialize to the error,
+    /// available via [`JsonPayloadError::rejected_snippet`]. By default nothing is captured.
+    ///
+    /// This is meant for debugging, the captured bytes may contain personal data.
+    pub fn capture_rejected(mut self, size: usize) -> Self {
+        self.capture_rejected = size;
+        self
+    }
+
+    /// Set status code of response for a body which is a valid json but violates
+    /// the constraints of model, e.g. `422 Unprocessable Entity`. By default status is `400`.
+    ///
+    /// Json syntax errors always respond with `400 Bad Request`.
+    pub fn validation_status(mut self, status: StatusCode) -> Self {
+        self.validation_status = status;
+        self
+    }
+
+    /// Register decoder for a custom `Content-Encoding`, e.g. `x-mycomp`.
+    ///
+    /// The decoder receives the payload stream and returns a stream of decoded bytes.
+    /// Custom decoders take precedence over the built-in ones of `compress` feature,
+    /// encodings which are neither registered nor built-in are still rejected.
+    pub fn decoder<F>(mut self, encoding: &str, decoder: F) -> Self
+    where
+        F: Fn(BodyStream) -> BodyStream + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.decoders).insert(encoding.to_ascii_lowercase(), Arc::new(decoder));
+        self
+    }
+
+    /// Accept media type of the model `T` declared by its [`JsonContentType`] for [`Json`]
+    /// extractor of the model, when no predicate is set by [`JsonConfig::content_type`].
+    pub fn model_content_type<T: JsonContentType + 'static>(mut self) -> Self {
+        Arc::make_mut(&mut self.model_content_types).insert(TypeId::of::<T>(), T::CONTENT_TYPE);
+        self
+    }
+
+    /// Config for extracting the model `T`, accepting its media type if registered and
+    /// limited by its schema if enabled.
+    pub(crate) fn for_model<T: Model + 'static>(mut self) -> Self {
+        if self.limit_from_schema && !self.limit_set {
+            if let Some(limit) = schema_limit::<T>() {
+                self.limit = self.limit.min(limit);
+            }
+        }
+        if self.content_type.is_some() {
+            return self;
+        }
+        match self.model_content_types.get(&TypeId::of::<T>()).copied() {
+            Some(content_type) => self.content_type(move |mime: mime::Mime| {
+                mime.essence_str().eq_ignore_ascii_case(content_type)
+            }),
+            None => self,
+        }
+    }
+
+    /// Render errors of [`Json`] extractor for the model `T` by its [`JsonErrorRender`].
+    pub fn render_errors<T: JsonErrorRender + 'static>(mut self) -> Self {
+        Arc::make_mut(&mut self.renderers).insert(TypeId::of::<T>(), T::error_response);
+        self
+    }
+
+    /// Count results of extractions with this config in `metrics`, in addition to
+    /// the [global](crate::metrics::global) metrics.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(mut self, metrics: Arc<JsonMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Observe sizes of bodies read with this config by `sink`, in addition to the
+    /// [body sizes](JsonMetrics::body_sizes) of global metrics and of
+    /// [`metrics`](JsonConfig::metrics).
+    ///
+    /// Sizes are observed by the extractor itself, so it is set per route by
+    /// the config of the route without a middleware.
+    #[cfg(feature = "metrics")]
+    pub fn body_size_sink(mut self, sink: Arc<dyn BodySizeSink>) -> Self {
+        self.size_sink = Some(sink);
+        self
+    }
+
+    /// Set hook run before the body is read, an error rejects the request without
+    /// reading the body.
+    ///
+    /// ```rust,ignore
+    /// JsonConfig::default().pre_parse(|req| match req.headers().get(USER_AGENT) {
+    ///     Some(agent) if agent == "bad-bot" => Err(JsonPayloadError::Rejected {
+    ///         status: StatusCode::FORBIDDEN,
+    ///         reason: "blocked user agent".to_string(),
+    ///     }),
+    ///     _ => Ok(()),
+    /// })
+    /// ```
+    pub fn pre_parse<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&HttpRequest) -> Result<(), JsonPayloadError> + Send + Sync + 'static,
+    {
+        self.pre_parse = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set hook run on the value after it is parsed and validated, an error rejects
+    /// the request. The value is the model of extractor, e.g. `User` for `Json<User>`,
+    /// and can be downcast to it. Extractors of several models, e.g.
+    /// [`OneOrMany`](crate::types::OneOrMany), run it on each model.
+    ///
+    /// Models report violations as errors only, there are no warnings which could be
+    /// collected instead of failing. Soft checks, e.g. of deprecated fields, can be done
+    /// by this hook, storing warnings in request extensions for the handler:
+    ///
+    /// ```rust,ignore
+    /// struct Warnings(Vec<String>);
+    ///
+    /// JsonConfig::default().post_parse(|req, value| {
+    ///     if let Some(user) = value.downcast_ref::<User>() {
+    ///         if user.nickname.is_some() {
+    ///             let warning = "`nickname` is deprecated".to_string();
+    ///             req.extensions_mut().insert(Warnings(vec![warning]));
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// })
+    /// ```
+    pub fn post_parse<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&HttpRequest, &dyn Any) -> Result<(), JsonPayloadError> + Send + Sync + 'static,
+    {
+        self.post_parse = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set hook rewriting the body before it is parsed, e.g. to normalize slightly
+    /// invalid json of a client, like `NaN` for `null`.
+    ///
+    /// It runs on the buffered bytes once the whole body is read and decoded, before
+    /// any check of the body, for each extractor reading json body. The hook runs on
+    /// the worker for every request, so rewriting costs an extra pass over the body,
+    /// and a copy if the body grows beyond its buffer.
+    ///
+    /// ```rust,ignore
+    /// JsonConfig::default().rewrite_body(|body: &mut BytesMut| {
+    ///     let fixed = String::from_utf8_lossy(body).replace("NaN", "null");
+    ///     *body = BytesMut::from(fixed.as_bytes());
+    /// })
+    /// ```
+    pub fn rewrite_body<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut BytesMut) + Send + Sync + 'static,
+    {
+        self.rewrite_body = Some(Arc::new(hook));
+        self
+    }
+
+    /// Abort reading of bodies with [`JsonPayloadError::Shutdown`] once the signal is
+    /// triggered, see [`ShutdownSignal`]. By default bodies are read until completion
+    /// during shutdown, so in-flight requests are fully drained.
+    pub fn abort_on_shutdown(mut self, signal: ShutdownSignal) -> Self {
+        self.shutdown = Some(signal);
+        self
+    }
+
+    /// Fail with [`JsonPayloadError::ReadTimeout`] responding `408 Request Timeout`, unless
+    /// the body is read within `timeout` since reading starts. By default reading is not
+    /// limited in time, besides timeouts of the server.
+    ///
+    /// With [`JsonConfig::parse_timeout`] it bounds the whole extraction, which takes at
+    /// most the sum of both for [`BlockingJson`](crate::types::BlockingJson). Other
+    /// extractors parse on the event loop, which can not be interrupted, so they reject
+    /// a body taking too long to parse once it is parsed. Timeouts of handlers are not
+    /// applied by the extractor, e.g. wrap the handler in `ntex::time::timeout` for that.
+    ///
+    /// ```rust,ignore
+    /// // a slow client gets 408, a body taking over a second to parse gets 400
+    /// JsonConfig::default()
+    ///     .read_timeout(Duration::from_secs(5))
+    ///     .parse_timeout(Duration::from_secs(1));
+    /// ```
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout.into());
+        self
+    }
+
+    /// Fail with [`JsonPayloadError::ParseTimeout`] responding `400 Bad Request`, unless
+    /// the body is parsed and validated within `timeout`. By default parsing is not
+    /// limited in time.
+    ///
+    /// It applies to every extractor parsing the body. Parsing can not be interrupted on
+    /// the event loop, so [`Json`] and the other extractors parsing inline reject the body
+    /// after parsing it, if it took longer than `timeout`. With a timeout
+    /// [`BlockingJson`](crate::types::BlockingJson) parses every body on the blocking
+    /// threadpool and fails as soon as the timeout elapses, the parsing still runs to
+    /// completion there and is discarded.
+    pub fn parse_timeout(mut self, timeout: Duration) -> Self {
+        self.parse_timeout = Some(timeout);
+        self
+    }
+
+    /// Cap allocations of json extractors for a body to `budget` bytes, failing with
+    /// [`JsonPayloadError::AllocBudget`] responding `413 Payload Too Large` beyond it.
+    /// By default only the size of body is limited.
+    ///
+    /// The budget accounts for the capacity of the buffer, which may be up to twice the
+    /// body as it grows, plus the length of the body for parsing, and is checked as each
+    /// chunk arrives, so reading stops before the buffer grows beyond it. Allocations of
+    /// the model itself are not tracked, the length of the body is an estimate of them.
+    /// Raw bytes of [`LimitedBytes`](crate::types::LimitedBytes) are not parsed and
+    /// unaffected.
+    pub fn alloc_budget(mut self, budget: usize) -> Self {
+        self.alloc_budget = Some(budget);
+        self
+    }
+
+    /// Reject requests with a body on the methods with [`JsonPayloadError::BodyNotAllowed`],
+    /// e.g. `[Method::GET, Method::DELETE]`. By default a body is accepted on any method.
+    ///
+    /// A request declares a body by non-zero `Content-Length` or by `Transfer-Encoding`.
+    pub fn forbid_body_on<I: IntoIterator<Item = Method>>(mut self, methods: I) -> Self {
+        self.forbid_body_on = methods.into_iter().collect();
+        self
+    }
+
+    /// Set hook called with the number of bytes read so far, each time a chunk of body
+    /// is read. The bytes are counted after decoding according to `Content-Encoding`.
+    ///
+    /// The hook runs on the worker reading the body, so it must be cheap and must not
+    /// block, e.g. store the count into an atomic for a progress UI to poll.
+    pub fn on_progress<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(usize) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set async hook run when a body is rejected, before the error response is produced.
+    ///
+    /// The hook can not change the response, it is meant for side effects like counting
+    /// rejections of a client:
+    ///
+    /// ```rust,ignore
+    /// JsonConfig::default().on_reject(|err, req| {
+    ///     let peer = req.peer_addr();
+    ///     async move { abuse::record(peer).await }
+    /// })
+    /// ```
+    pub fn on_reject<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(&JsonPayloadError, &HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.on_reject = Some(Arc::new(
+            move |err: &JsonPayloadError, req: &HttpRequest| -> PinBox<dyn Future<Output = ()>> {
+                Box::pin(hook(err, req))
+            },
+        ));
+        self
+    }
+
+    /// Check that request has no body if its method forbids it.
+    pub(crate) fn check_method(&self, req: &HttpRequest) -> Result<(), JsonPayloadError> {
+        if self.forbid_body_on.contains(req.method()) && has_body(req) {
+            Err(JsonPayloadError::BodyNotAllowed(req.method().clone()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Inspector of body chunks calling [`on_progress`](JsonConfig::on_progress) hook.
+    pub(crate) fn progress(&self) -> Option<InspectFn> {
+        let hook = self.on_progress.clone()?;
+        let mut read = 0;
+        Some(Box::new(move |chunk| {
+            read += chunk.len();
+            hook(read);
+            Ok(())
+        }))
+    }
+
+    /// Complete an extraction, counting its result with `metrics` feature and running
+    /// [`on_reject`](JsonConfig::on_reject) hook on error.
+    pub(crate) async fn complete<T>(&self, req: &HttpRequest, res: &Result<T, JsonPayloadError>) {
+        #[cfg(feature = "metrics")]
+        {
+            crate::metrics::global().record(res);
+            if let Some(ref metrics) = self.metrics {
+                metrics.record(res);
+            }
+        }
+
+        if let Err(err) = res {
+            mark_unread(req, err);
+        }
+        if let (Err(err), Some(hook)) = (res, &self.on_reject) {
+            hook(err, req).await;
+        }
+    }
+
+    /// Deserialize and validate buffered body according to config, on the current thread.
+    ///
+    /// An empty body, e.g. of a request with `Content-Length: 0`, fails with
+    /// [`JsonPayloadError::EmptyBody`]. Extractors accepting empty bodies check it before.
+    pub(crate) async fn parse<T>(
+        &self,
+        req: &HttpRequest,
+        body: BytesMut,
+    ) -> Result<T, JsonPayloadError>
+    where
+        T: Model + 'static,
+    {
+        let body = self.prepare::<T>(body)?;
+        let value = self.parse_within(|| parse_body(&body, self.capture_rejected))?;
+        self.run_post_parse(req, value)
+    }
+
+    /// Deserialize and validate buffered body same as [`parse`](JsonConfig::parse), but
+    /// on the blocking threadpool if it is larger than `above` bytes, or always with
+    /// [`JsonConfig::parse_timeout`].
+    pub(crate) async fn parse_blocking<T>(
+        &self,
+        req: &HttpRequest,
+        body: BytesMut,
+        above: usize,
+    ) -> Result<T, JsonPayloadError>
+    where
+        T: Model + Send + 'static,
+    {
+        let body = self.prepare::<T>(body)?;
+        let capture = self.capture_rejected;
+        let value = match self.parse_timeout {
+            Some(parse_timeout) => {
+                let task = ntex::rt::spawn_blocking(move || parse_body(&body, capture));
+                timeout(Millis::from(parse_timeout), task)
+                    .await
+                    .map_err(|_| JsonPayloadError::ParseTimeout)?
+                    .map_err(|_| JsonPayloadError::Blocking)??
+            }
+            None if body.len() > above => {
+                ntex::rt::spawn_blocking(move || parse_body(&body, capture))
+                    .await
+                    .map_err(|_| JsonPayloadError::Blocking)??
+            }
+            _ => parse_body(&body, capture)?,
+        };
+        self.run_post_parse(req, value)
+    }
+
+    /// Run `parse` on the current thread, failing with [`JsonPayloadError::ParseTimeout`]
+    /// if it took longer than [`JsonConfig::parse_timeout`].
+    pub(crate) fn parse_within<T, F>(&self, parse: F) -> Result<T, JsonPayloadError>
+    where
+        F: FnOnce() -> Result<T, JsonPayloadError>,
+    {
+        let started = Instant::now();
+        let res = parse();
+        match self.parse_timeout {
+            Some(limit) if started.elapsed() > limit => Err(JsonPayloadError::ParseTimeout),
+            _ => res,
+        }
+    }
+
+    /// Check the buffered body as a whole before it is parsed, that it is not empty,
+    /// and by [`JsonConfig::reject_trailing_bytes`] and [`JsonConfig::reject_duplicate_keys`].
+    pub(crate) fn check_body(&self, body: &[u8]) -> Result<(), JsonPayloadError> {
+        if body.is_empty() {
+            return Err(JsonPayloadError::EmptyBody);
+        }
+        if self.reject_trailing_bytes {
+            check_trailing_bytes(body, self.capture_rejected)?;
+        }
+        if self.reject_duplicate_keys {
+            check_duplicate_keys(body)?;
+        }
+        Ok(())
+    }
+
+    /// Check the body and apply options rewriting it before it is parsed into `T`.
+    fn prepare<T: Model>(&self, body: BytesMut) -> Result<BytesMut, JsonPayloadError> {
+        self.check_body(&body)?;
+        let body = match self.coerce_numeric_strings {
+            true => match coerce_numeric_strings::<T>(&body) {
+                Some(coerced) => BytesMut::from(&coerced[..]),
+                None => body,
+            },
+            false => body,
+        };
+        Ok(body)
+    }
+
+    /// Run [`post_parse`](JsonConfig::post_parse) hook on the parsed value.
+    pub(crate) fn run_post_parse<T: 'static>(
+        &self,
+        req: &HttpRequest,
+        value: T,
+    ) -> Result<T, JsonPayloadError> {
+        if let Some(ref hook) = self.post_parse {
+            hook(req, &value)?;
+        }
+        Ok(value)
+    }
 }
 
 impl Default for JsonConfig {
     fn default() -> Self {
         JsonConfig {
-            limit: 32768,
+            limit: default_json_limit(),
             content_type: None,
+            capture_rejected: 0,
+            validation_status: StatusCode::BAD_REQUEST,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            #[cfg(feature = "metrics")]
+            size_sink: None,
+            on_reject: None,
+            decoders: Arc::new(HashMap::new()),
+            application_wildcard: false,
+            wire_limit: None,
+            coalesce: 0,
+            compress_above: 1024,
+            decode_at_once_below: 16_384,
+            reject_duplicate_keys: false,
+            reject_trailing_bytes: false,
+            limit_set: false,
+            limit_from_schema: false,
+            on_progress: None,
+            forbid_body_on: Vec::new(),
+            response_charset: true,
+            strict_accept: false,
+            json_part: "metadata".to_string(),
+            json_errors: false,
+            locale_messages: None,
+            skip_content_type_check: false,
+            allow_empty_body: None,
+            preview: 256,
+            base64_body: false,
+            etag: false,
+            coerce_numeric_strings: false,
+            model_content_types: Arc::new(HashMap::new()),
+            renderers: Arc::new(HashMap::new()),
+            pre_parse: None,
+            post_parse: None,
+            rewrite_body: None,
+            shutdown: None,
+            read_timeout: None,
+            parse_timeout: None,
+            alloc_budget: None,
         }
     }
 }
 
-/// Request's payload json parser, it resolves to a deserialized `T` value.
+/// Request's payload json reader, it resolves to the buffered body.
 ///
 /// Returns error:
 ///
-/// * content type is not `application/json`
+/// * content type is not json
 ///   (unless specified in [`JsonConfig`](struct.JsonConfig.html))
-/// * content length is greater than 256k
-struct JsonBody<U, E> {
+/// * content length is greater than the limit of [`JsonConfig`], the
+///   [default limit](crate::set_default_json_limit) by default
+pub(crate) struct JsonBody {
     limit: usize,
+    wire_limit: Option<usize>,
+    coalesce: usize,
+    base64: bool,
+    inspect: Option<InspectFn>,
+    read_timeout: Option<Millis>,
+    rewrite: Option<RewriteFn>,
+    alloc_budget: Option<usize>,
+    #[cfg(feature = "metrics")]
+    sizes: Option<SizeRecorder>,
     length: Option<usize>,
-    #[cfg(feature = "compress")]
-    stream: Option<Decoder<Payload>>,
-    #[cfg(not(feature = "compress"))]
-    stream: Option<Payload>,
-    err: Option<E>,
-    fut: Option<PinBox<dyn Future<Output = Result<U, E>>>>,
+    stream: Option<BodyStream>,
+    err: Option<JsonPayloadError>,
+    fut: Option<PinBox<dyn Future<Output = Result<BytesMut, JsonPayloadError>>>>,
 }
 
-impl<U> JsonBody<U, JsonPayloadError>
-where
-    U: Model + 'static,
-{
+impl JsonBody {
     /// Create `JsonBody` for request.
-    fn new(
-        req: &HttpRequest,
-        payload: &mut Payload,
-        ctype: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
-    ) -> Self {
-        // check content-type
-        let json = if let Ok(Some(mime)) = req.mime_type() {
-            mime.subtype() == mime::JSON
-                || mime.suffix() == Some(mime::JSON)
-                || ctype.as_ref().map_or(false, |predicate| predicate(mime))
-        } else {
-            false
+    pub(crate) fn new(req: &HttpRequest, payload: &mut Payload, config: &JsonConfig) -> Self {
+        if let Err(e) = config.check_method(req) {
+            return JsonBody::err(e);
+        }
+        if let Some(ref hook) = config.pre_parse {
+            if let Err(e) = hook(req) {
+                return JsonBody::err(e);
+            }
+        }
+
+        // check content-type, parameters like `charset` quoted or not do not matter here
+        let base64 = config.base64_body
+            && matches!(req.mime_type(), Ok(Some(ref mime))
+                if mime.type_() == mime::TEXT && mime.subtype() == mime::PLAIN);
+        let json = match req.mime_type() {
+            _ if config.skip_content_type_check || base64 => true,
+            Ok(Some(mime)) => {
+                mime.subtype() == mime::JSON
+                    || mime.suffix() == Some(mime::JSON)
+                    || (cfg!(feature = "lenient-content-type")
+                        && mime.type_() == mime::TEXT
+                        && mime.subtype() == mime::PLAIN)
+                    || (config.application_wildcard && mime.type_() == mime::APPLICATION)
+                    || config
+                        .content_type
+                        .as_ref()
+                        .map_or(false, |predicate| predicate(req, mime))
+            }
+            Ok(None) => false,
+            Err(e) => return JsonBody::err(JsonPayloadError::MalformedContentType(e.to_string())),
         };
 
         if !json {
-            return JsonBody {
-                limit: 262_144,
-                length: None,
-                stream: None,
-                fut: None,
-                err: Some(JsonPayloadError::ContentType),
-            };
+            return JsonBody::err(JsonPayloadError::ContentType);
         }
 
-        let len = req
-            .headers()
-            .get("content-length")
-            .and_then(|l| l.to_str().ok())
-            .and_then(|s| s.parse::<usize>().ok());
+        let len = content_length(req);
+
+        let payload = match take_payload(req, payload, config) {
+            Ok(payload) => payload,
+            Err(e) => return JsonBody::err(e),
+        };
 
-        #[cfg(feature = "compress")]
-        let payload = Decoder::from_headers(payload.take(), req.headers());
-        #[cfg(not(feature = "compress"))]
-        let payload = payload.take();
+        #[cfg(feature = "metrics")]
+        let sizes = SizeRecorder::new(config.metrics.clone(), config.size_sink.clone());
+        let inspect = config.progress();
+        #[cfg(feature = "metrics")]
+        let inspect: Option<InspectFn> = {
+            let mut counter = sizes.counter();
+            Some(match inspect {
+                Some(mut progress) => Box::new(move |chunk| {
+                    counter(chunk)?;
+                    progress(chunk)
+                }),
+                None => Box::new(counter),
+            })
+        };
 
         JsonBody {
-            limit: 262_144,
+            limit: default_json_limit(),
+            wire_limit: config.wire_limit,
+            coalesce: config.coalesce,
+            base64,
+            inspect,
+            read_timeout: config.read_timeout,
+            rewrite: config.rewrite_body.clone(),
+            alloc_budget: config.alloc_budget,
+            #[cfg(feature = "metrics")]
+            sizes: Some(sizes),
             length: len,
             stream: Some(payload),
             fut: None,
@@ -156,18 +1326,328 @@ where
         }
     }
 
-    /// Change max size of payload. By default max size is 256Kb
-    fn limit(mut self, limit: usize) -> Self {
+    /// Create `JsonBody` which resolves to error immediately.
+    fn err(e: JsonPayloadError) -> Self {
+        JsonBody {
+            limit: default_json_limit(),
+            wire_limit: None,
+            coalesce: 0,
+            base64: false,
+            inspect: None,
+            read_timeout: None,
+            rewrite: None,
+            alloc_budget: None,
+            #[cfg(feature = "metrics")]
+            sizes: None,
+            length: None,
+            stream: None,
+            fut: None,
+            err: Some(e),
+        }
+    }
+
+    /// Change max size of payload. By default max size is the
+    /// [default limit](crate::set_default_json_limit).
+    pub(crate) fn limit(mut self, limit: usize) -> Self {
         self.limit = limit;
         self
     }
+
+    /// Inspect chunks of body as they arrive, an error aborts reading. Inspectors already
+    /// set, e.g. of [`JsonConfig::on_progress`], run first.
+    #[cfg(feature = "streaming")]
+    pub(crate) fn inspect(mut self, mut inspect: InspectFn) -> Self {
+        self.inspect = Some(match self.inspect.take() {
+            Some(mut first) => Box::new(move |chunk| {
+                first(chunk)?;
+                inspect(chunk)
+            }),
+            None => inspect,
+        });
+        self
+    }
 }
 
-impl<U> Future for JsonBody<U, JsonPayloadError>
-where
-    U: Model + 'static,
-{
-    type Output = Result<U, JsonPayloadError>;
+/// Deserialize and validate buffered body into `T`.
+///
+/// On failure the body is checked again to tell a json syntax error from a violation
+/// of the model. Up to `capture` leading bytes of the body are attached to the error.
+pub(crate) fn parse_body<T: Model>(body: &[u8], capture: usize) -> Result<T, JsonPayloadError> {
+    T::parse_bytes(body).map_err(|e| {
+        let snippet = snippet(body, capture);
+        match serde_json::from_slice::<serde::de::IgnoredAny>(body) {
+            Err(source) => syntax_error(body, source, snippet),
+            Ok(_) => JsonPayloadError::Deserialize { source: e, snippet },
+        }
+    })
+}
+
+/// Copy up to `capture` leading bytes of rejected body.
+pub(crate) fn snippet(body: &[u8], capture: usize) -> Option<Bytes> {
+    if capture > 0 {
+        Some(Bytes::copy_from_slice(&body[..body.len().min(capture)]))
+    } else {
+        None
+    }
+}
+
+/// Split buffered body into elements of a json array, without parsing them.
+pub(crate) fn split_array<'a>(
+    body: &'a [u8],
+    capture: usize,
+) -> Result<Vec<&'a serde_json::value::RawValue>, JsonPayloadError> {
+    serde_json::from_slice(body)
+        .map_err(|source| syntax_error(body, source, snippet(body, capture)))
+}
+
+/// Replace strings of numbers by numbers for top level fields of number types in schema
+/// of the model, `None` if nothing is replaced.
+fn coerce_numeric_strings<T: Model>(body: &[u8]) -> Option<Vec<u8>> {
+    use serde_json::{Map, Number, Value};
+
+    let mut object: Map<String, Value> = serde_json::from_slice(body).ok()?;
+    let schema: Value = serde_json::from_str(&T::schema()).ok()?;
+    let properties = schema["properties"].as_object()?;
+
+    let mut coerced = false;
+    for (name, value) in object.iter_mut() {
+        let text = match value {
+            Value::String(text) => text.trim(),
+            _ => continue,
+        };
+        let types = properties.get(name).map(|property| &property["type"]);
+        let allows = |t: &str| match types {
+            Some(Value::String(s)) => s == t,
+            Some(Value::Array(ts)) => ts.iter().any(|s| s == t),
+            _ => false,
+        };
+        let number = if allows("integer") && !allows("number") {
+            text.parse::<i64>()
+                .map(Number::from)
+                .or_else(|_| text.parse::<u64>().map(Number::from))
+                .ok()
+        } else if allows("number") {
+            text.parse::<i64>()
+                .map(Number::from)
+                .ok()
+                .or_else(|| text.parse::<f64>().ok().and_then(Number::from_f64))
+        } else {
+            None
+        };
+        if let Some(number) = number {
+            *value = Value::Number(number);
+            coerced = true;
+        }
+    }
+
+    if coerced {
+        serde_json::to_vec(&object).ok()
+    } else {
+        None
+    }
+}
+
+thread_local! {
+    /// Limits estimated from schemas by [`schema_limit`], per model.
+    static SCHEMA_LIMITS: std::cell::RefCell<HashMap<TypeId, Option<usize>>> =
+        std::cell::RefCell::new(HashMap::new());
+}
+
+/// Max size of body estimated from schema of the model `T`, `None` if it is unbounded.
+/// Estimated once per model and thread, see [`JsonConfig::limit_from_schema`].
+fn schema_limit<T: Model + 'static>() -> Option<usize> {
+    SCHEMA_LIMITS.with(|limits| {
+        *limits
+            .borrow_mut()
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                let schema: serde_json::Value = serde_json::from_str(&T::schema()).ok()?;
+                let size = estimate_size(&schema, &schema, 0)?;
+                Some(size.saturating_mul(2).max(1024))
+            })
+    })
+}
+
+/// Estimate the largest size of json value valid by the schema, `None` if unbounded.
+fn estimate_size(
+    schema: &serde_json::Value,
+    root: &serde_json::Value,
+    depth: usize,
+) -> Option<usize> {
+    use serde_json::Value;
+
+    // recursive models are unbounded
+    if depth > 32 {
+        return None;
+    }
+    if let Some(reference) = schema["$ref"].as_str() {
+        let target = root.pointer(reference.strip_prefix('#')?)?;
+        return estimate_size(target, root, depth + 1);
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(schemas) = schema[key].as_array() {
+            return schemas.iter().try_fold(0, |size, schema| {
+                Some(size.max(estimate_size(schema, root, depth + 1)?))
+            });
+        }
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        return values.iter().map(|value| value.to_string().len()).max();
+    }
+    if let Some(value) = schema.get("const") {
+        return Some(value.to_string().len());
+    }
+
+    let size_of = |t: &str| -> Option<usize> {
+        match t {
+            "null" => Some(4),
+            "boolean" => Some(5),
+            "integer" => Some(20),
+            "number" => Some(32),
+            // quoted, each char may be escaped as `\uXXXX`
+            "string" => Some(2 + 6 * schema["maxLength"].as_u64()? as usize),
+            "array" => {
+                let items = estimate_size(&schema["items"], root, depth + 1)?;
+                let len = schema["maxItems"].as_u64()? as usize;
+                Some(2 + len.checked_mul(items + 1)?)
+            }
+            "object" => schema["properties"].as_object()?.iter().try_fold(
+                2,
+                |size: usize, (name, property)| {
+                    let value = estimate_size(property, root, depth + 1)?;
+                    size.checked_add(name.len() + 4 + value)
+                },
+            ),
+            _ => None,
+        }
+    };
+    match &schema["type"] {
+        Value::String(t) => size_of(t),
+        Value::Array(ts) => ts
+            .iter()
+            .try_fold(0, |size, t| Some(size.max(size_of(t.as_str()?)?))),
+        _ => None,
+    }
+}
+
+/// Decode base64 text of body, ignoring whitespace like line breaks.
+fn decode_base64(body: &[u8]) -> Result<BytesMut, JsonPayloadError> {
+    let text: Vec<u8> = body
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    let decoded = base64::decode(text).map_err(JsonPayloadError::Base64)?;
+    Ok(BytesMut::from(&decoded[..]))
+}
+
+/// Check that no object of body has a duplicate key, syntax errors are left to the parser.
+pub(crate) fn check_duplicate_keys(body: &[u8]) -> Result<(), JsonPayloadError> {
+    use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+
+    /// Walk value, storing the first duplicate key found.
+    struct Check<'a>(&'a mut Option<String>);
+
+    impl<'de, 'a> DeserializeSeed<'de> for Check<'a> {
+        type Value = ();
+
+        fn deserialize<D: de::Deserializer<'de>>(self, d: D) -> Result<(), D::Error> {
+            d.deserialize_any(self)
+        }
+    }
+
+    impl<'de, 'a> Visitor<'de> for Check<'a> {
+        type Value = ();
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("any json value")
+        }
+
+        fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+            Ok(())
+        }
+
+        fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+            Ok(())
+        }
+
+        fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+            Ok(())
+        }
+
+        fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+            Ok(())
+        }
+
+        fn visit_str<E>(self, _: &str) -> Result<(), E> {
+            Ok(())
+        }
+
+        fn visit_unit<E>(self) -> Result<(), E> {
+            Ok(())
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+            while seq.next_element_seed(Check(&mut *self.0))?.is_some() {}
+            Ok(())
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+            let mut keys = std::collections::HashSet::new();
+            while let Some(key) = map.next_key::<String>()? {
+                if !keys.insert(key.clone()) {
+                    *self.0 = Some(key);
+                    return Err(de::Error::custom("duplicate key"));
+                }
+                map.next_value_seed(Check(&mut *self.0))?;
+            }
+            Ok(())
+        }
+    }
+
+    let mut duplicate = None;
+    let _ = Check(&mut duplicate).deserialize(&mut serde_json::Deserializer::from_slice(body));
+    match duplicate {
+        Some(key) => Err(JsonPayloadError::DuplicateKey(key)),
+        None => Ok(()),
+    }
+}
+
+/// Check that only whitespace follows the json value of body, syntax errors of the value
+/// are left to the parser.
+fn check_trailing_bytes(body: &[u8], capture: usize) -> Result<(), JsonPayloadError> {
+    use serde::Deserialize;
+
+    let mut de = serde_json::Deserializer::from_slice(body);
+    if serde::de::IgnoredAny::deserialize(&mut de).is_err() {
+        return Ok(());
+    }
+    de.end()
+        .map_err(|source| syntax_error(body, source, snippet(body, capture)))
+}
+
+/// Create syntax error, locating byte offset of the error in body.
+pub(crate) fn syntax_error(
+    body: &[u8],
+    source: serde_json::Error,
+    snippet: Option<Bytes>,
+) -> JsonPayloadError {
+    // line and column are 1-based, column 0 means the start of line
+    let line_start: usize = body
+        .split(|b| *b == b'\n')
+        .take(source.line().saturating_sub(1))
+        .map(|line| line.len() + 1)
+        .sum();
+    let offset = (line_start + source.column().saturating_sub(1)).min(body.len());
+    JsonPayloadError::Syntax {
+        source,
+        offset,
+        snippet,
+    }
+}
+
+impl Future for JsonBody {
+    type Output = Result<BytesMut, JsonPayloadError>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         if let Some(ref mut fut) = self.fut {
@@ -179,31 +1659,315 @@ where
         }
 
         let limit = self.limit;
-        if let Some(len) = self.length.take() {
+        let length = self.length.take();
+        if let Some(len) = length {
             if len > limit {
-                return Poll::Ready(Err(JsonPayloadError::Overflow));
+                let res = Err(JsonPayloadError::Overflow {
+                    limit,
+                    length: Some(len),
+                });
+                #[cfg(feature = "metrics")]
+                if let Some(ref sizes) = self.sizes {
+                    sizes.record(&res);
+                }
+                return Poll::Ready(res);
             }
         }
-        let mut stream = self.stream.take().unwrap();
+        let stream = self.stream.take().unwrap();
 
+        let fut = collect_body(
+            stream,
+            limit,
+            length,
+            self.wire_limit,
+            self.coalesce,
+            self.inspect.take(),
+            self.alloc_budget,
+        );
+        let fut = read_within(fut, self.read_timeout);
+        let base64 = self.base64;
+        let rewrite = self.rewrite.take();
+        #[cfg(feature = "metrics")]
+        let sizes = self.sizes.take();
         self.fut = Some(Box::pin(async move {
-            let mut body = BytesMut::with_capacity(8192);
-
-            while let Some(item) = stream_recv(&mut stream).await {
-                let chunk = item?;
-                if (body.len() + chunk.len()) > limit {
-                    return Err(JsonPayloadError::Overflow);
-                } else {
-                    body.extend_from_slice(&chunk);
+            let res = async move {
+                let mut body = match base64 {
+                    true => decode_base64(&fut.await?)?,
+                    false => fut.await?,
+                };
+                if let Some(rewrite) = rewrite {
+                    rewrite(&mut body);
                 }
+                Ok(body)
             }
-
-            match U::parse_bytes(&body) {
-                Ok(u) => Ok(u),
-                Err(e) => Err(JsonPayloadError::Deserialize(e)),
+            .await;
+            #[cfg(feature = "metrics")]
+            if let Some(sizes) = sizes {
+                sizes.record(&res);
             }
+            res
         }));
 
         self.poll(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use crate::types::OptionalJson;
+    use dade_derive::model;
+    use ntex::http::header::CONTENT_LENGTH;
+    use ntex::web::test::TestRequest;
+    use ntex::web::{DefaultError, WebResponseError};
+
+    #[model]
+    struct User {
+        #[field(min_length = 1, max_length = 10)]
+        name: String,
+    }
+
+    #[test]
+    fn test_limit_units() {
+        assert_eq!(JsonConfig::default().limit_kb(64).limit, 64 * 1024);
+        assert_eq!(JsonConfig::default().limit_mb(8).limit, 8 * 1024 * 1024);
+        assert_eq!(JsonConfig::default().limit_kb(0).limit, 0);
+        assert_eq!(JsonConfig::default().limit_kb(usize::MAX).limit, usize::MAX);
+        assert_eq!(JsonConfig::default().limit_mb(usize::MAX).limit, usize::MAX);
+    }
+
+    #[ntex::test]
+    async fn test_parse_timeout() {
+        let config = JsonConfig::default().parse_timeout(Duration::ZERO);
+        let req = request("application/json", r#"{"name":"axre"}"#).state(config);
+        let res = extract::<Json<User>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::ParseTimeout)));
+
+        let config = JsonConfig::default().parse_timeout(Duration::from_secs(60));
+        let req = request("application/json", r#"{"name":"axre"}"#).state(config);
+        assert_eq!(extract::<Json<User>>(req).await.unwrap().name, "axre");
+    }
+
+    #[ntex::test]
+    async fn test_reject_trailing_bytes() {
+        let body = r#"{"name":"axre"} garbage"#;
+        let config = JsonConfig::default().reject_trailing_bytes(true);
+        let req = request("application/json", body).state(config);
+        match extract::<Json<User>>(req).await {
+            Err(e @ JsonPayloadError::Syntax { .. }) => {
+                assert_eq!(e.syntax_position(), Some((1, 17, 16)));
+                assert_eq!(&body[16..], "garbage");
+            }
+            _ => panic!("expected a syntax error"),
+        }
+
+        let config = JsonConfig::default().reject_trailing_bytes(true);
+        let req = request("application/json", "{\"name\":\"axre\"} \r\n\t").state(config);
+        assert_eq!(extract::<Json<User>>(req).await.unwrap().name, "axre");
+    }
+
+    #[ntex::test]
+    async fn test_trailing_bytes_by_default() {
+        let describe = |res: Result<Json<User>, JsonPayloadError>| {
+            res.map(|user| user.into_inner().name)
+                .map_err(|e| e.error_code())
+        };
+        for body in [r#"{"name":"axre"} garbage"#, "{\"name\":\"axre\"} \n"] {
+            let req = request("application/json", body);
+            let extracted = describe(extract::<Json<User>>(req).await);
+            assert_eq!(extracted, describe(Json::from_slice(body.as_bytes())));
+        }
+    }
+
+    #[test]
+    fn test_parse_fuzz() {
+        assert!(matches!(
+            Json::<User>::parse_fuzz(b""),
+            Err(JsonPayloadError::EmptyBody)
+        ));
+        assert!(matches!(
+            Json::<User>::parse_fuzz(b"{\"name\":"),
+            Err(JsonPayloadError::Syntax { .. })
+        ));
+        let user = Json::<User>::parse_fuzz(br#"{"name":"axre"}"#).unwrap();
+        assert_eq!(user.name, "axre");
+    }
+
+    #[ntex::test]
+    async fn test_zero_content_length() {
+        let req = TestRequest::default()
+            .header(CONTENT_TYPE, "application/json")
+            .header(CONTENT_LENGTH, "0");
+        match extract::<Json<User>>(req).await {
+            Err(e @ JsonPayloadError::EmptyBody) => {
+                assert_eq!(e.status_code(), StatusCode::BAD_REQUEST)
+            }
+            _ => panic!("expected an empty body"),
+        }
+
+        let req = TestRequest::default()
+            .header(CONTENT_TYPE, "application/json")
+            .header(CONTENT_LENGTH, "0");
+        let user = extract::<OptionalJson<User>>(req).await.unwrap();
+        assert!(user.is_none());
+    }
+
+    #[ntex::test]
+    async fn test_malformed_content_type() {
+        let req = request("/////", r#"{"name":"axre"}"#);
+        match extract::<Json<User>>(req).await {
+            Err(e @ JsonPayloadError::MalformedContentType(_)) => {
+                assert_eq!(e.status_code(), StatusCode::BAD_REQUEST);
+                assert_eq!(e.error_code(), "malformed_content_type");
+            }
+            _ => panic!("expected a malformed content type"),
+        }
+
+        // a well-formed content type which is not json is still a content type error
+        let req = request("text/html", r#"{"name":"axre"}"#);
+        let res = extract::<Json<User>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::ContentType)));
+    }
+
+    /// Respond with `Json` of a user to the request.
+    async fn respond(req: TestRequest) -> HttpResponse {
+        let req = req.to_http_request();
+        let user = Json(User {
+            name: "axre".to_string(),
+        });
+        <Json<User> as Responder<DefaultError>>::respond_to(user, &req).await
+    }
+
+    #[ntex::test]
+    async fn test_strict_accept() {
+        let strict = || JsonConfig::default().strict_accept(true);
+        let cases = [
+            (
+                Some("application/xml"),
+                strict(),
+                StatusCode::NOT_ACCEPTABLE,
+            ),
+            (
+                Some("application/xml"),
+                JsonConfig::default(),
+                StatusCode::OK,
+            ),
+            (None, strict(), StatusCode::OK),
+            (None, JsonConfig::default(), StatusCode::OK),
+            (Some("application/*"), strict(), StatusCode::OK),
+            (
+                Some("application/json;q=0, */*"),
+                strict(),
+                StatusCode::NOT_ACCEPTABLE,
+            ),
+        ];
+        for (accept, config, status) in cases {
+            let mut req = TestRequest::default().state(config);
+            if let Some(accept) = accept {
+                req = req.header(ACCEPT, accept);
+            }
+            assert_eq!(respond(req).await.status(), status, "{:?}", accept);
+        }
+    }
+
+    #[ntex::test]
+    async fn test_response_charset() {
+        let res = respond(TestRequest::default()).await;
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json; charset=utf-8"
+        );
+
+        let config = JsonConfig::default().response_charset(false);
+        let res = respond(TestRequest::default().state(config)).await;
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "application/json");
+    }
+
+    #[ntex::test]
+    async fn test_response_encoding() {
+        use ntex::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+
+        let encode = |accept: Option<&str>, config: JsonConfig| {
+            let mut req = TestRequest::default().state(config);
+            if let Some(accept) = accept {
+                req = req.header(ACCEPT_ENCODING, accept);
+            }
+            respond(req)
+        };
+        let always = || JsonConfig::default().compress_above(0);
+        let cases = [
+            (Some("gzip"), always(), Some("gzip")),
+            (Some("br"), always(), Some("br")),
+            (Some("deflate;q=0.5, br"), always(), Some("br")),
+            (Some("gzip, deflate, br"), always(), Some("gzip")),
+            (Some("identity"), always(), None),
+            (None, always(), None),
+            // below the size to compress
+            (Some("gzip"), JsonConfig::default(), None),
+        ];
+        for (accept, config, encoding) in cases {
+            let res = encode(accept, config).await;
+            if cfg!(feature = "compress") {
+                let header = res.headers().get(CONTENT_ENCODING);
+                assert_eq!(
+                    header.map(|h| h.to_str().unwrap()),
+                    encoding,
+                    "{:?}",
+                    accept
+                );
+                assert_eq!(res.headers().get(VARY).unwrap(), "accept-encoding");
+            } else {
+                assert!(!res.headers().contains_key(CONTENT_ENCODING));
+                assert!(!res.headers().contains_key(VARY));
+            }
+        }
+
+        // compression disabled, the response does not depend on `Accept-Encoding`
+        let config = JsonConfig::default().compress_above(usize::MAX);
+        let res = encode(Some("gzip"), config).await;
+        assert!(!res.headers().contains_key(CONTENT_ENCODING));
+        assert!(!res.headers().contains_key(VARY));
+    }
+
+    #[test]
+    fn test_future_is_not_send() {
+        // ambiguous, so failing to compile, if the future is `Send`
+        trait AmbiguousIfSend<A> {
+            fn some_item() {}
+        }
+        impl<T: ?Sized> AmbiguousIfSend<()> for T {}
+        impl<T: ?Sized + Send> AmbiguousIfSend<u8> for T {}
+
+        fn assert_send<T: Send>() {}
+
+        type ExtractFuture = <Json<User> as FromRequest<DefaultError>>::Future;
+        let _ = <ExtractFuture as AmbiguousIfSend<_>>::some_item;
+        // the value is `Send` for a `Send` model, only the future holding the request is not
+        assert_send::<Json<User>>();
+    }
+
+    #[test]
+    fn test_config_precedence() {
+        let config = |limit| JsonConfig::default().limit(limit);
+        let limit = |req: &HttpRequest| JsonConfig::from_req(req).limit;
+
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(limit(&req), default_json_limit());
+
+        let req = TestRequest::default()
+            .app_state(State::new(config(3)))
+            .to_http_request();
+        assert_eq!(limit(&req), 3);
+
+        // `app_state` wins over `state` regardless of the level of registration
+        let req = TestRequest::default()
+            .app_state(State::new(config(3)))
+            .app_state(config(2))
+            .to_http_request();
+        assert_eq!(limit(&req), 2);
+
+        req.extensions_mut().insert(config(1));
+        assert_eq!(limit(&req), 1);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::util::BytesMut;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Json extractor accepting unknown fields, and reporting them, e.g. to log drift of
+/// clients before rejecting them.
+///
+/// Top level fields of a body of json object which are not properties in the schema of
+/// the model are removed before the body is validated same as [`Json`](crate::types::Json),
+/// and their names are kept in `unknown_fields`, sorted by name. Unknown fields of
+/// nested objects are left to the model.
+///
+/// The original body is checked by [`JsonConfig`] options before, e.g. for duplicate keys
+/// by [`JsonConfig::reject_duplicate_keys`], and the values of known fields are kept as
+/// they are sent, so numbers do not lose precision.
+pub struct JsonLenient<T> {
+    /// Parsed value
+    pub value: T,
+    /// Names of unknown top level fields of the body
+    pub unknown_fields: Vec<String>,
+}
+
+impl<T> JsonLenient<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> ops::Deref for JsonLenient<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> ops::DerefMut for JsonLenient<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for JsonLenient<T>
+where
+    T: Model + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) => match config.check_body(&body) {
+                    Err(e) => Err(e),
+                    Ok(()) => {
+                        let (body, unknown_fields) = match remove_unknown_fields::<T>(&body) {
+                            Some((stripped, unknown)) => (BytesMut::from(&stripped[..]), unknown),
+                            None => (body, Vec::new()),
+                        };
+                        config.parse(&req, body).await.map(|value| JsonLenient {
+                            value,
+                            unknown_fields,
+                        })
+                    }
+                },
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res
+        })
+    }
+}
+
+/// Remove top level fields which are not properties in schema of the model, returning
+/// the body without them and their names, `None` if there is no unknown field.
+///
+/// Values of known fields are copied as raw json text, so they are not parsed again.
+fn remove_unknown_fields<T: Model>(body: &[u8]) -> Option<(Vec<u8>, Vec<String>)> {
+    use serde_json::value::RawValue;
+    use serde_json::Value;
+    use std::collections::BTreeMap;
+
+    let object: BTreeMap<String, &RawValue> = serde_json::from_slice(body).ok()?;
+    let schema: Value = serde_json::from_str(&T::schema()).ok()?;
+    let properties = schema["properties"].as_object()?;
+
+    let (known, unknown): (Vec<_>, Vec<_>) = object
+        .into_iter()
+        .partition(|(name, _)| properties.contains_key(name));
+    if unknown.is_empty() {
+        return None;
+    }
+
+    let mut stripped = Vec::with_capacity(body.len());
+    stripped.push(b'{');
+    for (i, (name, value)) in known.iter().enumerate() {
+        if i > 0 {
+            stripped.push(b',');
+        }
+        serde_json::to_writer(&mut stripped, name).ok()?;
+        stripped.push(b':');
+        stripped.extend_from_slice(value.get().as_bytes());
+    }
+    stripped.push(b'}');
+    Some((
+        stripped,
+        unknown.into_iter().map(|(name, _)| name).collect(),
+    ))
+}
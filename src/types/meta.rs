@@ -0,0 +1,59 @@
+use crate::errors::JsonPayloadError;
+use crate::types::body::{content_encoding, content_length, has_body};
+use ntex::http::{HttpMessage, Payload};
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+
+/// Extractor of headers describing the body, without reading the body.
+///
+/// The headers are parsed same as by the extractors of bodies, so a malformed
+/// `Content-Type` fails with [`JsonPayloadError::MalformedContentType`] and an
+/// unsupported `Content-Encoding` with [`JsonPayloadError::UnsupportedEncoding`].
+/// The payload is left untouched, so a body extractor may follow it.
+///
+/// ```rust,ignore
+/// async fn index(meta: RequestMeta, user: Json<User>) -> String {
+///     format!("{} bytes of {:?}", meta.content_length.unwrap_or(0), meta.content_type)
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RequestMeta {
+    /// Media type of `Content-Type` header, if any
+    pub content_type: Option<mime::Mime>,
+    /// Length declared by `Content-Length` header, see
+    /// [`content_length`](crate::content_length)
+    pub content_length: Option<usize>,
+    /// Encoding of `Content-Encoding` header in lower case, `None` for `identity`
+    pub encoding: Option<String>,
+    /// Whether request declares a body, by a non-zero `Content-Length` or by
+    /// `Transfer-Encoding`
+    pub has_body: bool,
+}
+
+impl RequestMeta {
+    /// Parse headers of request.
+    pub fn of(req: &HttpRequest) -> Result<RequestMeta, JsonPayloadError> {
+        let content_type = req
+            .mime_type()
+            .map_err(|e| JsonPayloadError::MalformedContentType(e.to_string()))?;
+        Ok(RequestMeta {
+            content_type,
+            content_length: content_length(req),
+            encoding: content_encoding(req.headers())?,
+            has_body: has_body(req),
+        })
+    }
+
+    /// Whether the body is compressed according to `Content-Encoding` header.
+    pub fn is_compressed(&self) -> bool {
+        self.encoding.is_some()
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for RequestMeta {
+    type Error = JsonPayloadError;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        std::future::ready(RequestMeta::of(req))
+    }
+}
@@ -1,2 +1,60 @@
+pub mod blocking;
+pub(crate) mod body;
+pub mod bounded;
+pub mod bytes;
+pub mod content_type;
+pub mod dynamic;
+pub mod erased;
+pub(crate) mod header;
 pub mod json;
+pub mod lenient;
+pub mod meta;
+pub mod multi;
+pub mod multipart;
+pub mod ndjson;
+pub mod no_content;
+pub mod one_or_many;
+pub mod optional;
+pub mod patch;
+pub mod path;
+pub mod plain;
+pub mod preview;
+pub mod projection;
+#[cfg(feature = "json-schema")]
+pub mod schema;
+pub mod ser;
+pub mod sse;
+#[cfg(feature = "streaming")]
+pub mod streaming;
+pub mod timed;
+pub mod validate;
+pub mod value;
+pub use blocking::BlockingJson;
+pub use body::{BodyInfo, BodyStream};
+pub use bounded::BoundedJson;
+pub use bytes::LimitedBytes;
+pub use dynamic::{DynJson, DynSchema};
+pub use erased::{BodyExtractor, Extractor};
 pub use json::Json;
+pub use lenient::JsonLenient;
+pub use meta::RequestMeta;
+pub use multi::MultiJson;
+pub use multipart::{JsonPart, MultipartFile};
+pub use ndjson::NdJson;
+pub use no_content::NoContent;
+pub use one_or_many::OneOrMany;
+pub use optional::{JsonOrDefault, OptionalJson};
+pub use patch::{JsonPatch, PatchOp};
+pub use path::WithPath;
+pub use plain::{PlainJson, PlainJsonConfig, PlainJsonError};
+pub use preview::JsonWithPreview;
+pub use projection::{ProjectedJson, Projection};
+#[cfg(feature = "json-schema")]
+pub use schema::JsonSchemaValidator;
+pub use ser::{JsonSerOptions, JsonWithOptions, KeyCase};
+pub use sse::EventStream;
+#[cfg(feature = "streaming")]
+pub use streaming::StreamingJson;
+pub use timed::Timed;
+pub use validate::{ValidateOnly, ValidatedBytes};
+pub use value::JsonValue;
@@ -0,0 +1,120 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{parse_body, JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::any::Any;
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Tuple of models validating the same body, see [`MultiJson`].
+pub trait Models: Sized {
+    /// Parse and validate body by each model in order, failing with the first error.
+    fn parse(body: &[u8], capture: usize) -> Result<Self, JsonPayloadError>;
+
+    /// Pass each value to `f` in order, failing with the first error.
+    fn each(
+        &self,
+        f: &mut dyn FnMut(&dyn Any) -> Result<(), JsonPayloadError>,
+    ) -> Result<(), JsonPayloadError>;
+}
+
+macro_rules! impl_models {
+    ($($m:ident),+) => {
+        impl<$($m: Model + 'static),+> Models for ($($m,)+) {
+            fn parse(body: &[u8], capture: usize) -> Result<Self, JsonPayloadError> {
+                Ok(($(parse_body::<$m>(body, capture)?,)+))
+            }
+
+            #[allow(non_snake_case)]
+            fn each(
+                &self,
+                f: &mut dyn FnMut(&dyn Any) -> Result<(), JsonPayloadError>,
+            ) -> Result<(), JsonPayloadError> {
+                let ($($m,)+) = self;
+                $(f($m)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_models!(A);
+impl_models!(A, B);
+impl_models!(A, B, C);
+impl_models!(A, B, C, D);
+
+/// Json extractor validating one body by several models, e.g. `MultiJson<(User, Audit)>`.
+///
+/// The body can be taken by one extractor only, so two `Json` extractors of a handler
+/// can not share it. This extractor reads the body once and parses it by each model of
+/// the tuple, up to four models, resolving to a tuple of the values.
+///
+/// The body as a whole is checked once by [`JsonConfig`] options, e.g.
+/// [`JsonConfig::reject_duplicate_keys`], and [`JsonConfig::post_parse`] hook runs on
+/// each model.
+pub struct MultiJson<T>(pub T);
+
+impl<T> MultiJson<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for MultiJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Parse and validate bytes by each model with the default config.
+///
+/// No request is at hand, so none of [`JsonConfig`] options apply, e.g. duplicate keys
+/// are not checked and [`JsonConfig::post_parse`] hook does not run on the models.
+impl<T: Models> TryFrom<&[u8]> for MultiJson<T> {
+    type Error = JsonPayloadError;
+
+    fn try_from(body: &[u8]) -> Result<Self, Self::Error> {
+        if body.is_empty() {
+            return Err(JsonPayloadError::EmptyBody);
+        }
+        T::parse(body, 0).map(MultiJson)
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for MultiJson<T>
+where
+    T: Models + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) => config
+                    .check_body(&body)
+                    .and_then(|_| config.parse_within(|| T::parse(&body, config.capture_rejected)))
+                    .and_then(|values| match config.post_parse {
+                        Some(ref hook) => {
+                            values.each(&mut |value| hook(&req, value)).map(|_| values)
+                        }
+                        None => Ok(values),
+                    }),
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(MultiJson)
+        })
+    }
+}
@@ -0,0 +1,346 @@
+use crate::errors::JsonPayloadError;
+use crate::types::content_type::{param, unquote};
+use crate::types::json::{JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::{HttpMessage, Payload};
+use ntex::util::{Bytes, BytesMut};
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Part of a multipart body besides the json part, see [`JsonPart`].
+#[derive(Clone, Debug)]
+pub struct MultipartFile {
+    /// Name of the form field
+    pub name: String,
+    /// File name sent by the client, if any
+    pub filename: Option<String>,
+    /// Content type of the part, if any
+    pub content_type: Option<String>,
+    /// Content of the part
+    pub data: Bytes,
+}
+
+/// Extractor of a json model from a part of `multipart/form-data` body, e.g. metadata
+/// uploaded with a file.
+///
+/// The part is the one named by [`JsonConfig::json_part`], `metadata` by default, and it
+/// is validated by the model same as a body of [`Json`](crate::types::Json). Other parts
+/// are kept in `files`. The whole body is buffered, limited by [`JsonConfig`] limit, so
+/// the limit must allow for the files too.
+///
+/// A missing part fails with [`JsonPayloadError::MissingPart`], and a body which is not
+/// a valid multipart with [`JsonPayloadError::Multipart`].
+///
+/// ```rust,ignore
+/// async fn upload(upload: JsonPart<Photo>) -> String {
+///     format!("{} with {} files", upload.title, upload.files.len())
+/// }
+/// ```
+pub struct JsonPart<T> {
+    /// Parsed value of the json part
+    pub value: T,
+    /// Other parts of the body, in order of the body
+    pub files: Vec<MultipartFile>,
+}
+
+impl<T> JsonPart<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> ops::Deref for JsonPart<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> ops::DerefMut for JsonPart<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for JsonPart<T>
+where
+    T: Model + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let mut config = JsonConfig::from_req(req);
+        let boundary = match req.mime_type() {
+            Ok(Some(mime))
+                if mime.type_() == mime::MULTIPART && mime.subtype() == mime::FORM_DATA =>
+            {
+                param(&mime, "boundary")
+                    .filter(|boundary| !boundary.is_empty())
+                    .ok_or_else(|| JsonPayloadError::Multipart("missing boundary".to_string()))
+            }
+            Ok(_) => Err(JsonPayloadError::ContentType),
+            Err(e) => Err(JsonPayloadError::MalformedContentType(e.to_string())),
+        };
+        let boundary = match boundary {
+            Ok(boundary) => boundary,
+            Err(e) => {
+                let res = Err(e);
+                let req = req.clone();
+                return Box::pin(async move {
+                    config.complete(&req, &res).await;
+                    res
+                });
+            }
+        };
+
+        // the content type is checked above, other options of the config still apply
+        config.skip_content_type_check = true;
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) if body.is_empty() => Err(JsonPayloadError::EmptyBody),
+                Ok(body) => match split_parts(body.freeze(), &boundary) {
+                    Ok(parts) => {
+                        let mut json = None;
+                        let mut files = Vec::new();
+                        for part in parts {
+                            if json.is_none() && part.name == config.json_part {
+                                json = Some(part.data);
+                            } else {
+                                files.push(part);
+                            }
+                        }
+                        match json {
+                            Some(data) => config
+                                .parse(&req, BytesMut::from(&data[..]))
+                                .await
+                                .map(|value| JsonPart { value, files }),
+                            None => Err(JsonPayloadError::MissingPart(config.json_part.clone())),
+                        }
+                    }
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res
+        })
+    }
+}
+
+/// Split buffered multipart body into its parts.
+fn split_parts(body: Bytes, boundary: &str) -> Result<Vec<MultipartFile>, JsonPayloadError> {
+    let malformed = |reason: &str| JsonPayloadError::Multipart(reason.to_string());
+    let delimiter = format!("--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+
+    let mut at = find(&body, delimiter, 0).ok_or_else(|| malformed("missing boundary"))?;
+    let mut parts = Vec::new();
+    loop {
+        at += delimiter.len();
+        if body[at..].starts_with(b"--") {
+            return Ok(parts);
+        }
+        // transport padding may follow the delimiter before the line break
+        at = find(&body, b"\r\n", at).ok_or_else(|| malformed("unterminated boundary"))? + 2;
+
+        let headers_end =
+            find(&body, b"\r\n\r\n", at).ok_or_else(|| malformed("unterminated headers"))?;
+        let headers = std::str::from_utf8(&body[at..headers_end])
+            .map_err(|_| malformed("headers are not valid utf-8"))?;
+        let mut part = MultipartFile {
+            name: String::new(),
+            filename: None,
+            content_type: None,
+            data: Bytes::new(),
+        };
+        let mut disposition = false;
+        for header in headers.split("\r\n").filter(|header| !header.is_empty()) {
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| malformed("malformed header"))?;
+            let value = value.trim();
+            if name.trim().eq_ignore_ascii_case("content-disposition") {
+                disposition = true;
+                for item in value.split(';').skip(1).map(|item| item.trim()) {
+                    if let Some((key, value)) = item.split_once('=') {
+                        match key.trim().to_ascii_lowercase().as_str() {
+                            "name" => part.name = unquote(value.trim()),
+                            "filename" => part.filename = Some(unquote(value.trim())),
+                            _ => (),
+                        }
+                    }
+                }
+            } else if name.trim().eq_ignore_ascii_case("content-type") {
+                part.content_type = Some(value.to_string());
+            }
+        }
+        if !disposition {
+            return Err(malformed("part without content-disposition"));
+        }
+
+        let data_start = headers_end + 4;
+        let end = [b"\r\n".as_ref(), delimiter].concat();
+        let data_end =
+            find(&body, &end, data_start).ok_or_else(|| malformed("unterminated part"))?;
+        part.data = body.slice(data_start..data_end);
+        parts.push(part);
+
+        at = data_end + 2;
+    }
+}
+
+/// Find position of `needle` in `haystack` from `from`.
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|at| at + from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use dade_derive::model;
+
+    #[model]
+    struct Photo {
+        #[field(min_length = 1, max_length = 10)]
+        title: String,
+    }
+
+    const FORM: &str = "multipart/form-data; boundary=xyz";
+
+    /// Body of `--xyz` delimited parts, each given by its headers and content.
+    fn body(parts: &[(&str, &str)]) -> String {
+        let mut body = String::new();
+        for (headers, content) in parts {
+            body.push_str(&format!("--xyz\r\n{}\r\n\r\n{}\r\n", headers, content));
+        }
+        body.push_str("--xyz--\r\n");
+        body
+    }
+
+    fn reason(res: Result<Vec<MultipartFile>, JsonPayloadError>) -> String {
+        match res {
+            Err(JsonPayloadError::Multipart(reason)) => reason,
+            _ => panic!("expected a malformed multipart"),
+        }
+    }
+
+    #[ntex::test]
+    async fn test_extract() {
+        let body = body(&[
+            (
+                "Content-Disposition: form-data; name=\"metadata\"\r\nContent-Type: application/json",
+                r#"{"title":"sunset"}"#,
+            ),
+            (
+                "Content-Disposition: form-data; name=\"photo\"; filename=\"sunset.png\"\r\nContent-Type: image/png",
+                "\x00PNG\r\n",
+            ),
+        ]);
+        let upload = extract::<JsonPart<Photo>>(request(FORM, body))
+            .await
+            .unwrap();
+        assert_eq!(upload.title, "sunset");
+        assert_eq!(upload.files.len(), 1);
+        let file = &upload.files[0];
+        assert_eq!(file.name, "photo");
+        assert_eq!(file.filename.as_deref(), Some("sunset.png"));
+        assert_eq!(file.content_type.as_deref(), Some("image/png"));
+        assert_eq!(&file.data[..], b"\x00PNG\r\n");
+    }
+
+    #[ntex::test]
+    async fn test_missing_part() {
+        let body = body(&[("Content-Disposition: form-data; name=\"photo\"", "data")]);
+        let res = extract::<JsonPart<Photo>>(request(FORM, body.clone())).await;
+        assert!(matches!(res, Err(JsonPayloadError::MissingPart(ref name)) if name == "metadata"));
+
+        let config = JsonConfig::default().json_part("photo");
+        let res = extract::<JsonPart<Photo>>(request(FORM, body).state(config)).await;
+        assert!(matches!(res, Err(JsonPayloadError::Syntax { .. })));
+    }
+
+    #[ntex::test]
+    async fn test_missing_boundary() {
+        let body = body(&[(
+            "Content-Disposition: form-data; name=\"metadata\"",
+            r#"{"title":"sunset"}"#,
+        )]);
+        for content_type in ["multipart/form-data", "multipart/form-data; boundary=\"\""] {
+            let res = extract::<JsonPart<Photo>>(request(content_type, body.clone())).await;
+            assert!(
+                matches!(res, Err(JsonPayloadError::Multipart(ref reason)) if reason == "missing boundary"),
+                "{}",
+                content_type
+            );
+        }
+
+        // the boundary of the header is not in the body
+        let res = split_parts(Bytes::from(body), "abc");
+        assert_eq!(reason(res), "missing boundary");
+    }
+
+    #[test]
+    fn test_unterminated() {
+        let part = "--xyz\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\ndata";
+        assert_eq!(
+            reason(split_parts(Bytes::from(part), "xyz")),
+            "unterminated part"
+        );
+
+        let part = "--xyz\r\nContent-Disposition: form-data; name=\"a\"";
+        assert_eq!(
+            reason(split_parts(Bytes::from(part), "xyz")),
+            "unterminated headers"
+        );
+
+        assert_eq!(
+            reason(split_parts(Bytes::from("--xyz"), "xyz")),
+            "unterminated boundary"
+        );
+    }
+
+    #[test]
+    fn test_without_disposition() {
+        let body = body(&[("Content-Type: application/json", "{}")]);
+        assert_eq!(
+            reason(split_parts(Bytes::from(body), "xyz")),
+            "part without content-disposition"
+        );
+    }
+
+    #[test]
+    fn test_quoted_names() {
+        let body = body(&[
+            (
+                r#"content-disposition: form-data; filename="a \"b\".txt"; NAME="my file""#,
+                "a",
+            ),
+            (
+                "Content-Disposition: form-data; name=plain; filename=b.txt",
+                "b",
+            ),
+        ]);
+        let parts = split_parts(Bytes::from(body), "xyz").unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "my file");
+        assert_eq!(parts[0].filename.as_deref(), Some(r#"a "b".txt"#));
+        assert_eq!(&parts[0].data[..], b"a");
+        assert_eq!(parts[1].name, "plain");
+        assert_eq!(parts[1].filename.as_deref(), Some("b.txt"));
+        assert_eq!(parts[1].content_type, None);
+    }
+}
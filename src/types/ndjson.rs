@@ -0,0 +1,294 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{check_duplicate_keys, parse_body, JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::{HttpMessage, Payload};
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Extractor of newline delimited json, buffered into a vector of models.
+///
+/// The request must have `application/x-ndjson` content type. The body is read as a
+/// whole, limited by [`JsonConfig`] limit, then each line is validated by the model.
+/// Empty lines, e.g. the trailing one, are skipped.
+///
+/// At most `MAX_LINES` lines are allowed, otherwise extraction fails with
+/// [`JsonPayloadError::TooMany`] before any line is validated. A line longer than
+/// `LINE_LIMIT` bytes fails with [`JsonPayloadError::Overflow`]. Errors of a line are
+/// reported by [`JsonPayloadError::Line`] telling its 1-based number.
+///
+/// Each line is checked for duplicate keys by [`JsonConfig::reject_duplicate_keys`], and
+/// [`JsonConfig::post_parse`] hook runs on the model of each line.
+///
+/// ```rust,ignore
+/// async fn import(users: NdJson<User, 10_000>) -> String {
+///     format!("{} users", users.len())
+/// }
+/// ```
+pub struct NdJson<T, const MAX_LINES: usize = 1024, const LINE_LIMIT: usize = 65_536>(pub Vec<T>);
+
+impl<T, const MAX_LINES: usize, const LINE_LIMIT: usize> NdJson<T, MAX_LINES, LINE_LIMIT> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T, const MAX_LINES: usize, const LINE_LIMIT: usize> ops::Deref
+    for NdJson<T, MAX_LINES, LINE_LIMIT>
+{
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+
+impl<T, const MAX_LINES: usize, const LINE_LIMIT: usize> ops::DerefMut
+    for NdJson<T, MAX_LINES, LINE_LIMIT>
+{
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.0
+    }
+}
+
+impl<T, Err: ErrorRenderer, const MAX_LINES: usize, const LINE_LIMIT: usize> FromRequest<Err>
+    for NdJson<T, MAX_LINES, LINE_LIMIT>
+where
+    T: Model + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let mut config = JsonConfig::from_req(req);
+        let checked = match req.mime_type() {
+            Ok(Some(mime)) if mime.type_() == mime::APPLICATION && mime.subtype() == "x-ndjson" => {
+                Ok(())
+            }
+            Ok(_) => Err(JsonPayloadError::ContentType),
+            Err(e) => Err(JsonPayloadError::MalformedContentType(e.to_string())),
+        };
+        if let Err(e) = checked {
+            let res = Err(e);
+            let req = req.clone();
+            return Box::pin(async move {
+                config.complete(&req, &res).await;
+                res
+            });
+        }
+
+        // the content type is checked above, other options of the config still apply
+        config.skip_content_type_check = true;
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) => config.parse_within(|| {
+                    parse_lines(&body, MAX_LINES, LINE_LIMIT, |line| {
+                        if config.reject_duplicate_keys {
+                            check_duplicate_keys(line)?;
+                        }
+                        let value = parse_body(line, config.capture_rejected)?;
+                        config.run_post_parse(&req, value)
+                    })
+                }),
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(NdJson)
+        })
+    }
+}
+
+/// Parse and validate bytes with the default config, within the line limits of the type.
+///
+/// No request is at hand, so none of [`JsonConfig`] options apply, e.g. duplicate keys
+/// are not checked and [`JsonConfig::post_parse`] hook does not run for the lines.
+impl<T: Model, const MAX_LINES: usize, const LINE_LIMIT: usize> TryFrom<&[u8]>
+    for NdJson<T, MAX_LINES, LINE_LIMIT>
+{
+    type Error = JsonPayloadError;
+
+    fn try_from(body: &[u8]) -> Result<Self, Self::Error> {
+        parse_lines(body, MAX_LINES, LINE_LIMIT, |line| parse_body(line, 0)).map(NdJson)
+    }
+}
+
+/// Split body into lines, parsing each non-empty line by `parse`.
+fn parse_lines<T, F>(
+    body: &[u8],
+    max: usize,
+    line_limit: usize,
+    mut parse: F,
+) -> Result<Vec<T>, JsonPayloadError>
+where
+    F: FnMut(&[u8]) -> Result<T, JsonPayloadError>,
+{
+    if body.is_empty() {
+        return Err(JsonPayloadError::EmptyBody);
+    }
+    let lines: Vec<(usize, &[u8])> = body
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .enumerate()
+        .filter(|(_, line)| !line.iter().all(u8::is_ascii_whitespace))
+        .map(|(index, line)| (index + 1, line))
+        .collect();
+    if lines.len() > max {
+        return Err(JsonPayloadError::TooMany {
+            max,
+            len: lines.len(),
+        });
+    }
+
+    lines
+        .into_iter()
+        .map(|(line, bytes)| {
+            let res = if bytes.len() > line_limit {
+                Err(JsonPayloadError::Overflow {
+                    limit: line_limit,
+                    length: Some(bytes.len()),
+                })
+            } else {
+                parse(bytes)
+            };
+            res.map_err(|source| JsonPayloadError::Line {
+                line,
+                source: Box::new(source),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use dade_derive::model;
+    use ntex::http::StatusCode;
+
+    #[model]
+    struct User {
+        #[field(min_length = 1, max_length = 10)]
+        name: String,
+    }
+
+    const NDJSON: &str = "application/x-ndjson";
+
+    #[ntex::test]
+    async fn test_extract() {
+        let body = "{\"name\":\"a\"}\r\n\r\n{\"name\":\"b\"}\r\n";
+        let users = extract::<NdJson<User>>(request(NDJSON, body))
+            .await
+            .unwrap();
+        let names: Vec<_> = users.iter().map(|user| user.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+
+        let users = NdJson::<User>::try_from("{\"name\":\"a\"}\n".as_bytes()).unwrap();
+        assert_eq!(users.len(), 1);
+    }
+
+    #[ntex::test]
+    async fn test_content_type() {
+        let body = "{\"name\":\"a\"}\n";
+        for content_type in ["application/json", "text/plain", "/////"] {
+            let res = extract::<NdJson<User>>(request(content_type, body)).await;
+            assert!(
+                matches!(
+                    res,
+                    Err(JsonPayloadError::ContentType | JsonPayloadError::MalformedContentType(_))
+                ),
+                "{}",
+                content_type
+            );
+        }
+    }
+
+    #[ntex::test]
+    async fn test_line_number() {
+        // empty lines are skipped but still counted
+        let body = "{\"name\":\"a\"}\n\n{\"name\":\"\"}\n";
+        match extract::<NdJson<User>>(request(NDJSON, body)).await {
+            Err(JsonPayloadError::Line { line, source }) => {
+                assert_eq!(line, 3);
+                assert!(matches!(*source, JsonPayloadError::Deserialize { .. }));
+            }
+            _ => panic!("expected an invalid line"),
+        }
+
+        let body = "{\"name\":\"a\"}\r\n{\"name\":";
+        match extract::<NdJson<User>>(request(NDJSON, body)).await {
+            Err(JsonPayloadError::Line { line, source }) => {
+                assert_eq!(line, 2);
+                assert!(matches!(*source, JsonPayloadError::Syntax { .. }));
+            }
+            _ => panic!("expected an invalid line"),
+        }
+    }
+
+    #[ntex::test]
+    async fn test_max_lines() {
+        let body = "{\"name\":\"a\"}\n".repeat(3);
+        let users = extract::<NdJson<User, 3>>(request(NDJSON, body.clone())).await;
+        assert_eq!(users.unwrap().len(), 3);
+
+        let body = body + "{\"name\":\"\"}\n";
+        let res = extract::<NdJson<User, 3>>(request(NDJSON, body)).await;
+        assert!(matches!(
+            res,
+            Err(JsonPayloadError::TooMany { max: 3, len: 4 })
+        ));
+    }
+
+    #[ntex::test]
+    async fn test_line_limit() {
+        let body = "{\"name\":\"a\"}\n{\"name\":\"abcdefghij\"}\n";
+        match extract::<NdJson<User, 8, 16>>(request(NDJSON, body)).await {
+            Err(JsonPayloadError::Line { line, source }) => {
+                assert_eq!(line, 2);
+                assert!(matches!(
+                    *source,
+                    JsonPayloadError::Overflow {
+                        limit: 16,
+                        length: Some(21)
+                    }
+                ));
+            }
+            _ => panic!("expected a line over the limit"),
+        }
+    }
+
+    #[ntex::test]
+    async fn test_config_checks() {
+        let body = "{\"name\":\"a\"}\n{\"name\":\"b\",\"name\":\"c\"}\n";
+        let config = JsonConfig::default().reject_duplicate_keys(true);
+        match extract::<NdJson<User>>(request(NDJSON, body).state(config)).await {
+            Err(JsonPayloadError::Line { line, source }) => {
+                assert_eq!(line, 2);
+                assert!(matches!(*source, JsonPayloadError::DuplicateKey(_)));
+            }
+            _ => panic!("expected a duplicate key"),
+        }
+
+        let config =
+            JsonConfig::default().post_parse(|_, value| match value.downcast_ref::<User>() {
+                Some(user) if user.name == "b" => Err(JsonPayloadError::Rejected {
+                    status: StatusCode::CONFLICT,
+                    reason: "b is taken".to_string(),
+                }),
+                _ => Ok(()),
+            });
+        let body = "{\"name\":\"a\"}\n{\"name\":\"b\"}\n";
+        match extract::<NdJson<User>>(request(NDJSON, body).state(config)).await {
+            Err(JsonPayloadError::Line { line, source }) => {
+                assert_eq!(line, 2);
+                assert!(matches!(*source, JsonPayloadError::Rejected { .. }));
+            }
+            _ => panic!("expected a rejected line"),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use ntex::web::{ErrorRenderer, HttpRequest, HttpResponse, Responder};
+
+/// Responder of `204 No Content` without a body, e.g. for delete endpoints among handlers
+/// responding with [`Json`](crate::types::Json).
+///
+/// `Json(())` can not respond with it, since the responder of `Json` is implemented for
+/// any model.
+///
+/// ```rust,ignore
+/// async fn delete(path: web::types::Path<u64>) -> Result<NoContent, JsonPayloadError> {
+///     remove_user(path.into_inner()).await?;
+///     Ok(NoContent)
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NoContent;
+
+impl<Err: ErrorRenderer> Responder<Err> for NoContent {
+    type Future = std::future::Ready<HttpResponse>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        std::future::ready(HttpResponse::NoContent().finish())
+    }
+}
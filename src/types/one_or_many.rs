@@ -0,0 +1,105 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{parse_body, split_array, JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Json extractor accepting either a single model or an array of models, e.g. for
+/// ingestion of clients sending one or many events to the same endpoint.
+///
+/// A single value resolves to a vector of one element. Each element of an array is
+/// validated by the model, a failing element is reported by [`JsonPayloadError::Element`]
+/// telling its 0-based index. The body as a whole is checked by [`JsonConfig`] options,
+/// e.g. [`JsonConfig::reject_duplicate_keys`], and [`JsonConfig::post_parse`] hook runs on
+/// each element.
+pub struct OneOrMany<T>(pub Vec<T>);
+
+impl<T> OneOrMany<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for OneOrMany<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for OneOrMany<T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.0
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for OneOrMany<T>
+where
+    T: Model + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) => config
+                    .check_body(&body)
+                    .and_then(|_| {
+                        config.parse_within(|| parse_one_or_many(&body, config.capture_rejected))
+                    })
+                    .and_then(|values| {
+                        values
+                            .into_iter()
+                            .map(|value| config.run_post_parse(&req, value))
+                            .collect()
+                    }),
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(OneOrMany)
+        })
+    }
+}
+
+/// Parse and validate bytes with the default config, as a single value or an array.
+///
+/// No request is at hand, so none of [`JsonConfig`] options apply, e.g. duplicate keys
+/// are not checked and [`JsonConfig::post_parse`] hook does not run.
+impl<T: Model> TryFrom<&[u8]> for OneOrMany<T> {
+    type Error = JsonPayloadError;
+
+    fn try_from(body: &[u8]) -> Result<Self, Self::Error> {
+        parse_one_or_many(body, 0).map(OneOrMany)
+    }
+}
+
+fn parse_one_or_many<T: Model>(body: &[u8], capture: usize) -> Result<Vec<T>, JsonPayloadError> {
+    match body.iter().find(|b| !b.is_ascii_whitespace()) {
+        _ if body.is_empty() => Err(JsonPayloadError::EmptyBody),
+        Some(b'[') => split_array(body, capture)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, element)| {
+                parse_body(element.get().as_bytes(), capture).map_err(|source| {
+                    JsonPayloadError::Element {
+                        index,
+                        source: Box::new(source),
+                    }
+                })
+            })
+            .collect(),
+        _ => parse_body(body, capture).map(|value| vec![value]),
+    }
+}
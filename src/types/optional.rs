@@ -0,0 +1,213 @@
+use crate::errors::JsonPayloadError;
+use crate::types::body::has_body;
+use crate::types::json::{parse_body, JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Json extractor for an optional body.
+///
+/// Resolves to `None` when the request has no body, otherwise the body is checked and
+/// validated same as [`Json`](crate::types::Json).
+///
+/// Note the difference from `Json<Option<T>>`, which still requires a body and accepts
+/// `null` as `None`, and from a model whose fields are optional, which requires an object.
+/// `Json<Option<T>>` can not mean "optional body" itself, because it overlaps with the
+/// implementation of `Json<T>` for any model.
+pub struct OptionalJson<T>(pub Option<T>);
+
+impl<T> OptionalJson<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> Option<T> {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for OptionalJson<T> {
+    type Target = Option<T>;
+
+    fn deref(&self) -> &Option<T> {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for OptionalJson<T> {
+    fn deref_mut(&mut self) -> &mut Option<T> {
+        &mut self.0
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for OptionalJson<T>
+where
+    T: Model + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        if !has_body(req) {
+            return Box::pin(async { Ok(OptionalJson(None)) });
+        }
+
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) if body.is_empty() => Ok(None),
+                Ok(body) => config.parse(&req, body).await.map(Some),
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(OptionalJson)
+        })
+    }
+}
+
+/// Json extractor falling back to default value for an empty body.
+///
+/// Resolves to `T::default()` when the request has no body or an empty one, otherwise
+/// the body is checked and validated same as [`Json`](crate::types::Json). The default
+/// value is validated by the model too, so a default violating the model is an error,
+/// and [`JsonConfig::post_parse`] hook runs on it same as on a parsed body.
+///
+/// Unlike [`OptionalJson`], the handler can not tell an empty body from a body equal
+/// to the default value.
+pub struct JsonOrDefault<T>(pub T);
+
+impl<T> JsonOrDefault<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for JsonOrDefault<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for JsonOrDefault<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for JsonOrDefault<T>
+where
+    T: Model + Default + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+        let req = req.clone();
+
+        if !has_body(&req) {
+            return Box::pin(async move {
+                let res = default_value(&config, &req);
+                config.complete(&req, &res).await;
+                res.map(JsonOrDefault)
+            });
+        }
+
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) if body.is_empty() => default_value(&config, &req),
+                Ok(body) => config.parse(&req, body).await,
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(JsonOrDefault)
+        })
+    }
+}
+
+/// Validate the default value of model and run [`JsonConfig::post_parse`] hook on it,
+/// same as a parsed body.
+fn default_value<T>(config: &JsonConfig, req: &HttpRequest) -> Result<T, JsonPayloadError>
+where
+    T: Model + Default + 'static,
+{
+    parse_body(T::default().json(false).as_bytes(), 0)
+        .and_then(|value| config.run_post_parse(req, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::extract;
+    use dade_derive::model;
+    use ntex::http::StatusCode;
+    use ntex::web::test::TestRequest;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[model]
+    struct User {
+        #[field(min_length = 1, max_length = 10)]
+        name: String,
+    }
+
+    impl Default for User {
+        fn default() -> Self {
+            User {
+                name: "default".to_string(),
+            }
+        }
+    }
+
+    #[ntex::test]
+    async fn test_default_post_parse() {
+        let config =
+            JsonConfig::default().post_parse(|_, value| match value.downcast_ref::<User>() {
+                Some(user) if user.name == "default" => Err(JsonPayloadError::Rejected {
+                    status: StatusCode::UNPROCESSABLE_ENTITY,
+                    reason: "a user is required".to_string(),
+                }),
+                _ => Ok(()),
+            });
+        let req = TestRequest::default().state(config.clone());
+        let res = extract::<JsonOrDefault<User>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::Rejected { .. })));
+
+        let req = crate::test::request("application/json", "").state(config);
+        let res = extract::<JsonOrDefault<User>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::Rejected { .. })));
+    }
+
+    #[ntex::test]
+    async fn test_default_completes() {
+        let rejected = Arc::new(AtomicUsize::new(0));
+        let counter = rejected.clone();
+        let config = JsonConfig::default()
+            .post_parse(|_, _| {
+                Err(JsonPayloadError::Rejected {
+                    status: StatusCode::UNPROCESSABLE_ENTITY,
+                    reason: "rejected".to_string(),
+                })
+            })
+            .on_reject(move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async {}
+            });
+        let req = TestRequest::default().state(config);
+        assert!(extract::<JsonOrDefault<User>>(req).await.is_err());
+        assert_eq!(rejected.load(Ordering::SeqCst), 1);
+
+        let req = TestRequest::default();
+        let user = extract::<JsonOrDefault<User>>(req).await.unwrap();
+        assert_eq!(user.name, "default");
+    }
+}
@@ -0,0 +1,296 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{snippet, syntax_error, JsonBody, JsonConfig};
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use serde_json::{Map, Value};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Operation of json patch, see [RFC 6902](https://www.rfc-editor.org/rfc/rfc6902).
+///
+/// Paths are json pointers, e.g. `/users/0/name`, they are checked to be pointers but
+/// not resolved.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatchOp {
+    /// Add the value at the path, inserting into an array or setting a member
+    Add {
+        /// Location to add the value at
+        path: String,
+        /// Value to add, may be `null`
+        value: Value,
+    },
+    /// Remove the value at the path
+    Remove {
+        /// Location of the value to remove
+        path: String,
+    },
+    /// Replace the value at the path
+    Replace {
+        /// Location of the value to replace
+        path: String,
+        /// Value replacing the existing one, may be `null`
+        value: Value,
+    },
+    /// Remove the value at `from` and add it at the path
+    Move {
+        /// Location of the value to move
+        from: String,
+        /// Location to move the value to
+        path: String,
+    },
+    /// Copy the value at `from` to the path
+    Copy {
+        /// Location of the value to copy
+        from: String,
+        /// Location to copy the value to
+        path: String,
+    },
+    /// Test that the value at the path equals the value
+    Test {
+        /// Location of the value to test
+        path: String,
+        /// Value expected at the path, may be `null`
+        value: Value,
+    },
+}
+
+/// Json patch extractor, e.g. for a body of `application/json-patch+json`.
+///
+/// The body must be an array of operations, a malformed operation fails with
+/// [`JsonPayloadError::InvalidPatch`] telling its 0-based index. The body is checked by
+/// [`JsonConfig`] options, e.g. [`JsonConfig::reject_duplicate_keys`], so an operation
+/// can not be made ambiguous by repeating `op`.
+pub struct JsonPatch(pub Vec<PatchOp>);
+
+impl JsonPatch {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> Vec<PatchOp> {
+        self.0
+    }
+}
+
+impl ops::Deref for JsonPatch {
+    type Target = Vec<PatchOp>;
+
+    fn deref(&self) -> &Vec<PatchOp> {
+        &self.0
+    }
+}
+
+impl ops::DerefMut for JsonPatch {
+    fn deref_mut(&mut self) -> &mut Vec<PatchOp> {
+        &mut self.0
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for JsonPatch {
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) => config.check_body(&body).and_then(|_| {
+                    config.parse_within(|| parse_patch(&body, config.capture_rejected))
+                }),
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(JsonPatch)
+        })
+    }
+}
+
+/// Parse bytes with the default config, failing with the first malformed operation.
+///
+/// No request is at hand, so none of [`JsonConfig`] options apply, e.g. duplicate keys
+/// are not checked and no part of the body is captured into a syntax error.
+impl TryFrom<&[u8]> for JsonPatch {
+    type Error = JsonPayloadError;
+
+    fn try_from(body: &[u8]) -> Result<Self, Self::Error> {
+        parse_patch(body, 0).map(JsonPatch)
+    }
+}
+
+/// Parse body of json patch, failing with the first malformed operation.
+fn parse_patch(body: &[u8], capture: usize) -> Result<Vec<PatchOp>, JsonPayloadError> {
+    if body.is_empty() {
+        return Err(JsonPayloadError::EmptyBody);
+    }
+    serde_json::from_slice::<Vec<Value>>(body)
+        .map_err(|source| syntax_error(body, source, snippet(body, capture)))?
+        .into_iter()
+        .enumerate()
+        .map(|(index, op)| {
+            let op = match op {
+                Value::Object(op) => parse_op(op),
+                _ => Err("operation is not an object".to_string()),
+            };
+            op.map_err(|reason| JsonPayloadError::InvalidPatch { index, reason })
+        })
+        .collect()
+}
+
+/// Parse operation of patch, failing with the reason.
+fn parse_op(mut op: Map<String, Value>) -> Result<PatchOp, String> {
+    let name = match op.remove("op") {
+        Some(Value::String(name)) => name,
+        Some(_) => return Err("`op` is not a string".to_string()),
+        None => return Err("missing `op`".to_string()),
+    };
+    let path = pointer(&mut op, "path")?;
+    let op = match name.as_str() {
+        "remove" => PatchOp::Remove { path },
+        "move" => PatchOp::Move {
+            from: pointer(&mut op, "from")?,
+            path,
+        },
+        "copy" => PatchOp::Copy {
+            from: pointer(&mut op, "from")?,
+            path,
+        },
+        "add" | "replace" | "test" => {
+            // `null` is a valid value, only a missing member is an error
+            let value = op
+                .remove("value")
+                .ok_or_else(|| "missing `value`".to_string())?;
+            match name.as_str() {
+                "add" => PatchOp::Add { path, value },
+                "replace" => PatchOp::Replace { path, value },
+                _ => PatchOp::Test { path, value },
+            }
+        }
+        _ => return Err(format!("unknown operation `{}`", name)),
+    };
+    Ok(op)
+}
+
+/// Take json pointer member of operation.
+fn pointer(op: &mut Map<String, Value>, member: &str) -> Result<String, String> {
+    match op.remove(member) {
+        Some(Value::String(pointer)) if pointer.is_empty() || pointer.starts_with('/') => {
+            Ok(pointer)
+        }
+        Some(_) => Err(format!("`{}` is not a json pointer", member)),
+        None => Err(format!("missing `{}`", member)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+
+    /// Reason of the invalid operation at `index` of the body.
+    fn invalid(body: &str, index: usize) -> String {
+        match JsonPatch::try_from(body.as_bytes()) {
+            Err(JsonPayloadError::InvalidPatch { index: i, reason }) if i == index => reason,
+            _ => panic!("expected invalid operation {} of {}", index, body),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let body = r#"[
+            {"op":"add","path":"/a","value":1},
+            {"op":"remove","path":"/a"},
+            {"op":"replace","path":"","value":null},
+            {"op":"move","from":"/a","path":"/b"},
+            {"op":"copy","from":"/b","path":"/c"},
+            {"op":"test","path":"/c","value":null}
+        ]"#;
+        let patch = JsonPatch::try_from(body.as_bytes()).unwrap();
+        assert_eq!(
+            patch.into_inner(),
+            [
+                PatchOp::Add {
+                    path: "/a".to_string(),
+                    value: Value::from(1)
+                },
+                PatchOp::Remove {
+                    path: "/a".to_string()
+                },
+                PatchOp::Replace {
+                    path: "".to_string(),
+                    value: Value::Null
+                },
+                PatchOp::Move {
+                    from: "/a".to_string(),
+                    path: "/b".to_string()
+                },
+                PatchOp::Copy {
+                    from: "/b".to_string(),
+                    path: "/c".to_string()
+                },
+                PatchOp::Test {
+                    path: "/c".to_string(),
+                    value: Value::Null
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_operations() {
+        let cases = [
+            (r#"["add"]"#, "operation is not an object"),
+            (r#"[{"path":"/a"}]"#, "missing `op`"),
+            (r#"[{"op":1,"path":"/a"}]"#, "`op` is not a string"),
+            (
+                r#"[{"op":"delete","path":"/a"}]"#,
+                "unknown operation `delete`",
+            ),
+            (r#"[{"op":"remove"}]"#, "missing `path`"),
+            (
+                r#"[{"op":"remove","path":"a"}]"#,
+                "`path` is not a json pointer",
+            ),
+            (
+                r#"[{"op":"remove","path":1}]"#,
+                "`path` is not a json pointer",
+            ),
+            (r#"[{"op":"move","path":"/a"}]"#, "missing `from`"),
+            (
+                r#"[{"op":"copy","from":"b","path":"/a"}]"#,
+                "`from` is not a json pointer",
+            ),
+            (r#"[{"op":"add","path":"/a"}]"#, "missing `value`"),
+            (r#"[{"op":"test","path":"/a"}]"#, "missing `value`"),
+        ];
+        for (body, reason) in cases {
+            assert_eq!(invalid(body, 0), reason, "{}", body);
+        }
+
+        let body = r#"[{"op":"remove","path":"/a"},{"op":"remove","path":"/b"},{"op":"add"}]"#;
+        assert_eq!(invalid(body, 2), "missing `path`");
+    }
+
+    #[test]
+    fn test_not_an_array() {
+        let res = JsonPatch::try_from(r#"{"op":"remove","path":"/a"}"#.as_bytes());
+        assert!(matches!(res, Err(JsonPayloadError::Syntax { .. })));
+        let res = JsonPatch::try_from(&b""[..]);
+        assert!(matches!(res, Err(JsonPayloadError::EmptyBody)));
+    }
+
+    #[ntex::test]
+    async fn test_duplicate_keys() {
+        let body = r#"[{"op":"add","op":"remove","path":"/a","value":1}]"#;
+        let req = request("application/json-patch+json", body);
+        let patch = extract::<JsonPatch>(req).await.unwrap();
+        assert!(matches!(patch[0], PatchOp::Remove { .. }));
+
+        let config = JsonConfig::default().reject_duplicate_keys(true);
+        let req = request("application/json-patch+json", body).state(config);
+        let res = extract::<JsonPatch>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::DuplicateKey(_))));
+    }
+}
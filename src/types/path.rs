@@ -0,0 +1,102 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{Json, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::web::error::PathError;
+use ntex::web::types::Path;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Extractor of path parameters and json body together.
+///
+/// Path parameters are extracted first, and the body is not read when they fail,
+/// so an error of path always takes precedence over an error of body. It completes the
+/// extraction same as errors of body, e.g. [`JsonConfig::on_reject`] hook runs on it.
+///
+/// ```rust,ignore
+/// async fn update(req: WithPath<(u32,), User>) -> String {
+///     format!("Update {} to {}", req.path.0, req.body.name)
+/// }
+/// ```
+pub struct WithPath<P, B> {
+    /// Path parameters
+    pub path: P,
+    /// Validated body
+    pub body: B,
+}
+
+impl<P, B> WithPath<P, B> {
+    /// Deconstruct to path parameters and body
+    pub fn into_inner(self) -> (P, B) {
+        (self.path, self.body)
+    }
+}
+
+impl<P, B, Err: ErrorRenderer> FromRequest<Err> for WithPath<P, B>
+where
+    P: DeserializeOwned + 'static,
+    B: Model + 'static,
+    Path<P>: FromRequest<Err, Error = PathError>,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let path = <Path<P> as FromRequest<Err>>::from_request(req, payload);
+        let req = req.clone();
+        let mut payload = payload.take();
+        Box::pin(async move {
+            let path = match path.await {
+                Ok(path) => path.into_inner(),
+                Err(e) => {
+                    let res: Result<Self, _> = Err(JsonPayloadError::Path(e));
+                    JsonConfig::from_req(&req)
+                        .for_model::<B>()
+                        .complete(&req, &res)
+                        .await;
+                    return res;
+                }
+            };
+            let body = <Json<B> as FromRequest<Err>>::from_request(&req, &mut payload).await?;
+            Ok(WithPath {
+                path,
+                body: body.into_inner(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::request;
+    use dade_derive::model;
+    use ntex::http::ConnectionType;
+    use ntex::web::{DefaultError, WebResponseError};
+
+    #[model]
+    struct User {
+        name: String,
+    }
+
+    #[ntex::test]
+    async fn test_path_error() {
+        let (req, mut payload) = request("application/json", r#"{"name":"axre"}"#)
+            .param("id", "axre")
+            .to_http_parts();
+        let res =
+            <WithPath<(u32,), User> as FromRequest<DefaultError>>::from_request(&req, &mut payload)
+                .await;
+        match res {
+            Err(e @ JsonPayloadError::Path(_)) => {
+                let res = e.error_response(&req);
+                assert_eq!(res.head().connection_type(), ConnectionType::Close);
+            }
+            _ => panic!("expected an error of path"),
+        }
+    }
+}
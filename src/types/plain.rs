@@ -0,0 +1,52 @@
+crate::define_body_extractor! {
+    /// Json extractor for any type deserialized by serde, without validation of a model.
+    ///
+    /// Any `*/json` or `*/*+json` content type is json, same as [`Json`](crate::types::Json).
+    /// The limit is set by [`PlainJsonConfig`] registered with `app_state`, by default 32Kb.
+    /// None of the options of [`JsonConfig`](crate::types::json::JsonConfig) apply.
+    ///
+    /// ```rust,ignore
+    /// async fn index(tags: PlainJson<Vec<String>>) -> String {
+    ///     tags.join(", ")
+    /// }
+    /// ```
+    pub struct PlainJson, PlainJsonConfig, PlainJsonError;
+    matches = |mime: &mime::Mime| {
+        mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON)
+    };
+    decode = |body: &[u8]| serde_json::from_slice(body);
+    bound = serde::de::DeserializeOwned;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use std::collections::HashMap;
+
+    #[ntex::test]
+    async fn test_extract() {
+        let req = request("application/vnd.api+json", r#"{"a":1,"b":2}"#);
+        let map = extract::<PlainJson<HashMap<String, u32>>>(req)
+            .await
+            .unwrap();
+        assert_eq!(map["a"], 1);
+        assert_eq!(map["b"], 2);
+    }
+
+    #[ntex::test]
+    async fn test_errors() {
+        let req = request("text/plain", "{}");
+        let res = extract::<PlainJson<serde_json::Value>>(req).await;
+        assert!(matches!(res, Err(PlainJsonError::ContentType)));
+
+        let req =
+            request("application/json", "[1,2,3]").app_state(PlainJsonConfig::default().limit(4));
+        let res = extract::<PlainJson<serde_json::Value>>(req).await;
+        assert!(matches!(res, Err(PlainJsonError::Overflow { limit: 4 })));
+
+        let req = request("application/json", r#"{"a":"x"}"#);
+        let res = extract::<PlainJson<HashMap<String, u32>>>(req).await;
+        assert!(matches!(res, Err(PlainJsonError::Decode(_))));
+    }
+}
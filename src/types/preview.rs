@@ -0,0 +1,74 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{snippet, JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Json extractor with a preview of the raw body, e.g. for audit logs.
+///
+/// The body is checked and validated same as [`Json`](crate::types::Json). `preview` is
+/// up to [`JsonConfig::preview`] leading bytes of the body, with invalid utf-8 replaced,
+/// so a full body is never kept besides the value.
+pub struct JsonWithPreview<T> {
+    /// Parsed value
+    pub value: T,
+    /// Leading bytes of the raw body
+    pub preview: String,
+}
+
+impl<T> JsonWithPreview<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> ops::Deref for JsonWithPreview<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> ops::DerefMut for JsonWithPreview<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for JsonWithPreview<T>
+where
+    T: Model + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) => {
+                    let preview = snippet(&body, config.preview)
+                        .map(|preview| String::from_utf8_lossy(&preview).into_owned())
+                        .unwrap_or_default();
+                    config
+                        .parse(&req, body)
+                        .await
+                        .map(|value| JsonWithPreview { value, preview })
+                }
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res
+        })
+    }
+}
@@ -0,0 +1,373 @@
+use crate::errors::{JsonPayloadError, SchemaViolation};
+use crate::types::json::{snippet, syntax_error, JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use serde_json::{Map, Value};
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops;
+use std::pin::Pin;
+use std::rc::Rc;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Set of fields projected by [`ProjectedJson`].
+///
+/// ```rust,ignore
+/// struct NameOnly;
+///
+/// impl Projection for NameOnly {
+///     const FIELDS: &'static [&'static str] = &["name"];
+/// }
+///
+/// async fn rename(body: ProjectedJson<User, NameOnly>) -> HttpResponse { ... }
+/// ```
+pub trait Projection {
+    /// Json keys of the projected fields of the model, each must be a property in the
+    /// schema of the model.
+    const FIELDS: &'static [&'static str];
+}
+
+/// Json extractor validating only projected fields of a model, e.g. for partial updates.
+///
+/// The body must be a json object. Fields of the projection present in the body are
+/// validated by their properties in the schema of the model `T`, and fields outside of
+/// the projection are ignored, so the other fields of the model are not validated at
+/// all. Resolves to the projected fields present in the body. A violation fails with
+/// [`JsonPayloadError::Schema`]. The body as a whole is checked by [`JsonConfig`]
+/// options, e.g. [`JsonConfig::reject_duplicate_keys`].
+///
+/// The schema is checked for the common keywords of types, `enum`, `const`, lengths of
+/// strings and arrays, bounds of numbers, `items`, `properties`, `required` and `$ref`.
+/// Constraints of the model which its schema does not declare are not checked.
+///
+/// A field of the projection which is not a property of the model is a mistake of the
+/// projection, extraction fails with [`JsonPayloadError::InvalidProjection`] responding
+/// `500 Internal Server Error` without reading the body. The fields are checked once
+/// per projection and worker thread.
+///
+/// Only top level fields can be projected, nested projections like `"address.city"`
+/// are not supported, validate the whole nested object instead.
+pub struct ProjectedJson<T, F> {
+    /// Projected fields present in the body
+    pub fields: Map<String, Value>,
+    _marker: PhantomData<fn() -> (T, F)>,
+}
+
+impl<T, F> ProjectedJson<T, F> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> Map<String, Value> {
+        self.fields
+    }
+}
+
+impl<T, F> ops::Deref for ProjectedJson<T, F> {
+    type Target = Map<String, Value>;
+
+    fn deref(&self) -> &Map<String, Value> {
+        &self.fields
+    }
+}
+
+impl<T, F> ops::DerefMut for ProjectedJson<T, F> {
+    fn deref_mut(&mut self) -> &mut Map<String, Value> {
+        &mut self.fields
+    }
+}
+
+impl<T, F, Err: ErrorRenderer> FromRequest<Err> for ProjectedJson<T, F>
+where
+    T: Model + 'static,
+    F: Projection + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+        let schema = projected_schema::<T, F>();
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match schema {
+                Ok(schema) => match fut.await {
+                    Ok(body) => config.check_body(&body).and_then(|_| {
+                        config
+                            .parse_within(|| project::<F>(&body, &schema, config.capture_rejected))
+                    }),
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(|fields| ProjectedJson {
+                fields,
+                _marker: PhantomData,
+            })
+        })
+    }
+}
+
+thread_local! {
+    /// Schemas of models checked by [`projected_schema`], per model and projection.
+    static SCHEMAS: RefCell<HashMap<TypeId, Result<Rc<Value>, String>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Schema of the model `T`, checked that each field of the projection `F` is a property
+/// of it. Checked once per model, projection and thread.
+fn projected_schema<T, F>() -> Result<Rc<Value>, JsonPayloadError>
+where
+    T: Model + 'static,
+    F: Projection + 'static,
+{
+    SCHEMAS
+        .with(|schemas| {
+            schemas
+                .borrow_mut()
+                .entry(TypeId::of::<(T, F)>())
+                .or_insert_with(|| {
+                    let schema: Value = serde_json::from_str(&T::schema()).unwrap_or_default();
+                    match F::FIELDS
+                        .iter()
+                        .find(|field| schema["properties"].get(**field).is_none())
+                    {
+                        Some(field) => Err(field.to_string()),
+                        None => Ok(Rc::new(schema)),
+                    }
+                })
+                .clone()
+        })
+        .map_err(JsonPayloadError::InvalidProjection)
+}
+
+fn project<F: Projection>(
+    body: &[u8],
+    schema: &Value,
+    capture: usize,
+) -> Result<Map<String, Value>, JsonPayloadError> {
+    if body.is_empty() {
+        return Err(JsonPayloadError::EmptyBody);
+    }
+    let mut fields: Map<String, Value> = serde_json::from_slice(body)
+        .map_err(|source| syntax_error(body, source, snippet(body, capture)))?;
+    fields.retain(|key, _| F::FIELDS.contains(&key.as_str()));
+
+    let mut violations = Vec::new();
+    for (name, value) in &fields {
+        let path = format!("/{}", escape_pointer(name));
+        check(
+            &schema["properties"][name],
+            schema,
+            value,
+            &path,
+            0,
+            &mut violations,
+        );
+    }
+    if violations.is_empty() {
+        Ok(fields)
+    } else {
+        Err(JsonPayloadError::Schema { violations })
+    }
+}
+
+/// Check `value` against the schema, pushing a violation for each failing keyword.
+fn check(
+    schema: &Value,
+    root: &Value,
+    value: &Value,
+    path: &str,
+    depth: usize,
+    violations: &mut Vec<SchemaViolation>,
+) {
+    // references of recursive models are followed up to a depth
+    if depth > 32 {
+        return;
+    }
+    if let Some(reference) = schema["$ref"].as_str() {
+        if let Some(target) = reference.strip_prefix('#').and_then(|p| root.pointer(p)) {
+            check(target, root, value, path, depth + 1, violations);
+        }
+        return;
+    }
+    let mut violate = |message: String| {
+        violations.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(t) => vec![t.as_str()],
+        Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+        _ => Vec::new(),
+    };
+    let is = |t: &str| match t {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    };
+    if !types.is_empty() && !types.iter().any(|t| is(t)) {
+        violate(format!("{} is not of type {}", value, types.join(" or ")));
+        return;
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        if !values.contains(value) {
+            violate(format!("{} is not one of the allowed values", value));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            violate(format!("{} is not {}", value, expected));
+        }
+    }
+
+    match value {
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema["minLength"].as_u64().filter(|min| len < *min) {
+                violate(format!("string is shorter than {} characters", min));
+            }
+            if let Some(max) = schema["maxLength"].as_u64().filter(|max| len > *max) {
+                violate(format!("string is longer than {} characters", max));
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            let bound = |key: &str| schema[key].as_f64();
+            if let Some(min) = bound("minimum").filter(|min| n < *min) {
+                violate(format!("{} is less than {}", n, min));
+            }
+            if let Some(max) = bound("maximum").filter(|max| n > *max) {
+                violate(format!("{} is greater than {}", n, max));
+            }
+            if let Some(min) = bound("exclusiveMinimum").filter(|min| n <= *min) {
+                violate(format!("{} is not greater than {}", n, min));
+            }
+            if let Some(max) = bound("exclusiveMaximum").filter(|max| n >= *max) {
+                violate(format!("{} is not less than {}", n, max));
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as u64;
+            if let Some(min) = schema["minItems"].as_u64().filter(|min| len < *min) {
+                violate(format!("array has fewer than {} items", min));
+            }
+            if let Some(max) = schema["maxItems"].as_u64().filter(|max| len > *max) {
+                violate(format!("array has more than {} items", max));
+            }
+            if schema["items"].is_object() {
+                for (i, item) in items.iter().enumerate() {
+                    let path = format!("{}/{}", path, i);
+                    check(&schema["items"], root, item, &path, depth + 1, violations);
+                }
+            }
+        }
+        Value::Object(object) => {
+            for name in schema["required"].as_array().into_iter().flatten() {
+                if let Some(name) = name.as_str().filter(|name| !object.contains_key(*name)) {
+                    violate(format!("missing required property `{}`", name));
+                }
+            }
+            for (name, item) in object {
+                if let Some(property) = schema["properties"].get(name) {
+                    let path = format!("{}/{}", path, escape_pointer(name));
+                    check(property, root, item, &path, depth + 1, violations);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Escape a key for a json pointer.
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use dade_derive::model;
+    use ntex::http::StatusCode;
+    use ntex::web::WebResponseError;
+
+    #[model]
+    struct User {
+        #[field(min_length = 1, max_length = 10)]
+        name: String,
+        #[field(ge = 0)]
+        age: i64,
+    }
+
+    struct NameOnly;
+
+    impl Projection for NameOnly {
+        const FIELDS: &'static [&'static str] = &["name"];
+    }
+
+    struct Unknown;
+
+    impl Projection for Unknown {
+        const FIELDS: &'static [&'static str] = &["name", "email"];
+    }
+
+    #[ntex::test]
+    async fn test_projection() {
+        let req = request("application/json", r#"{"name":"axre","age":-1}"#);
+        let body = extract::<ProjectedJson<User, NameOnly>>(req).await.unwrap();
+        assert_eq!(body.len(), 1);
+        assert_eq!(body["name"], "axre");
+
+        let req = request("application/json", r#"{"name":""}"#);
+        let res = extract::<ProjectedJson<User, NameOnly>>(req).await;
+        match res {
+            Err(JsonPayloadError::Schema { violations }) => assert_eq!(violations[0].path, "/name"),
+            _ => panic!("expected a schema violation"),
+        }
+    }
+
+    #[ntex::test]
+    async fn test_config_checks() {
+        let body = r#"{"name":"","name":"axre"}"#;
+        let req = request("application/json", body);
+        let fields = extract::<ProjectedJson<User, NameOnly>>(req).await.unwrap();
+        assert_eq!(fields["name"], "axre");
+
+        let config = JsonConfig::default().reject_duplicate_keys(true);
+        let req = request("application/json", body).state(config);
+        let res = extract::<ProjectedJson<User, NameOnly>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::DuplicateKey(_))));
+
+        let config = JsonConfig::default().reject_trailing_bytes(true);
+        let req = request("application/json", r#"{"name":"axre"} {}"#).state(config);
+        let res = extract::<ProjectedJson<User, NameOnly>>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::Syntax { .. })));
+    }
+
+    #[ntex::test]
+    async fn test_unknown_field() {
+        for _ in 0..2 {
+            let req = request("application/json", r#"{"name":"axre"}"#);
+            let res = extract::<ProjectedJson<User, Unknown>>(req).await;
+            match res {
+                Err(e @ JsonPayloadError::InvalidProjection(_)) => {
+                    assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR)
+                }
+                _ => panic!("expected an invalid projection"),
+            }
+        }
+    }
+}
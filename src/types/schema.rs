@@ -0,0 +1,128 @@
+//! Validation of bodies by json schemas loaded at runtime, with `json-schema` feature.
+//!
+//! ```rust,ignore
+//! let schema = DynSchema::new(JsonSchemaValidator::from_file("schemas/user.json")?);
+//! web::resource("/users").state(schema).route(web::post().to(create));
+//!
+//! async fn create(user: DynJson) -> String {
+//!     format!("Welcome {}!", user["name"])
+//! }
+//! ```
+use crate::errors::{JsonPayloadError, SchemaViolation};
+use crate::types::dynamic::DynValidator;
+use crate::types::json::{snippet, syntax_error};
+use jsonschema::JSONSchema;
+use std::io;
+use std::path::Path;
+
+/// Validator of bodies by a json schema, to be registered by
+/// [`DynSchema::new`](crate::types::DynSchema::new) for [`DynJson`](crate::types::DynJson).
+///
+/// A body violating the schema fails with [`JsonPayloadError::Schema`], listing every
+/// violation with the json pointer of the invalid value.
+pub struct JsonSchemaValidator(JSONSchema);
+
+impl JsonSchemaValidator {
+    /// Compile the json schema, failing with the description of an invalid schema.
+    pub fn new(schema: &serde_json::Value) -> Result<Self, String> {
+        JSONSchema::compile(schema)
+            .map(JsonSchemaValidator)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Load and compile the json schema from a file, e.g. at startup.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let schema = serde_json::from_slice(&std::fs::read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        JsonSchemaValidator::new(&schema).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl DynValidator for JsonSchemaValidator {
+    fn validate(&self, body: &[u8], capture: usize) -> Result<(), JsonPayloadError> {
+        let value: serde_json::Value = serde_json::from_slice(body)
+            .map_err(|source| syntax_error(body, source, snippet(body, capture)))?;
+        self.0
+            .validate(&value)
+            .map_err(|errors| JsonPayloadError::Schema {
+                violations: errors
+                    .map(|e| SchemaViolation {
+                        path: e.instance_path.to_string(),
+                        message: e.to_string(),
+                    })
+                    .collect(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{extract, request};
+    use crate::types::{DynJson, DynSchema};
+    use ntex::http::StatusCode;
+    use ntex::web::WebResponseError;
+
+    fn schema() -> DynSchema {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 1},
+                "tags": {"type": "array", "items": {"type": "string"}},
+            },
+            "required": ["name"],
+        });
+        DynSchema::new(JsonSchemaValidator::new(&schema).unwrap())
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        let schema = serde_json::json!({"type": "no-such-type"});
+        assert!(JsonSchemaValidator::new(&schema).is_err());
+    }
+
+    #[ntex::test]
+    async fn test_extract() {
+        let req = request("application/json", r#"{"name":"axre","tags":["a"]}"#).state(schema());
+        let user = extract::<DynJson>(req).await.unwrap();
+        assert_eq!(user["name"], "axre");
+        assert_eq!(user["tags"][0], "a");
+    }
+
+    #[ntex::test]
+    async fn test_violations() {
+        let req = request("application/json", r#"{"name":"","tags":["a",1]}"#).state(schema());
+        let e = match extract::<DynJson>(req).await {
+            Err(e @ JsonPayloadError::Schema { .. }) => e,
+            _ => panic!("expected schema violations"),
+        };
+        assert_eq!(e.status_code(), StatusCode::BAD_REQUEST);
+        let violations = match e {
+            JsonPayloadError::Schema { ref violations } => violations,
+            _ => unreachable!(),
+        };
+        let mut paths: Vec<_> = violations.iter().map(|v| v.path.as_str()).collect();
+        paths.sort_unstable();
+        assert_eq!(paths, ["/name", "/tags/1"]);
+
+        let problem = e.problem();
+        assert_eq!(problem["error"], e.error_code());
+        assert_eq!(problem["violations"].as_array().unwrap().len(), 2);
+
+        let req = request("application/json", r#"{"tags":[]}"#).state(schema());
+        match extract::<DynJson>(req).await {
+            Err(JsonPayloadError::Schema { violations }) => {
+                assert_eq!(violations.len(), 1);
+                assert_eq!(violations[0].path, "");
+            }
+            _ => panic!("expected a missing property"),
+        }
+    }
+
+    #[ntex::test]
+    async fn test_syntax_error() {
+        let req = request("application/json", r#"{"name":"#).state(schema());
+        let res = extract::<DynJson>(req).await;
+        assert!(matches!(res, Err(JsonPayloadError::Syntax { .. })));
+    }
+}
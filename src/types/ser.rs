@@ -0,0 +1,248 @@
+use crate::types::json::{respond_json, Json};
+use dade::Model;
+use ntex::web::{ErrorRenderer, HttpRequest, HttpResponse, Responder};
+use serde_json::{Map, Value};
+
+/// Naming convention of keys of json objects in responses, see [`JsonSerOptions`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyCase {
+    /// Keys as serialized by the model
+    Preserve,
+    /// `camelCase` keys, e.g. `userName` for `user_name`
+    Camel,
+    /// `snake_case` keys, e.g. `user_name` for `userName`. A run of capitals is taken as
+    /// an acronym, e.g. `http_server` for `HTTPServer` and `user_id` for `userID`.
+    Snake,
+}
+
+/// Options of serializing responses of [`Json::with_options`].
+///
+/// By default the output of the model is kept as is.
+#[derive(Clone, Copy, Debug)]
+pub struct JsonSerOptions {
+    skip_nulls: bool,
+    key_case: KeyCase,
+}
+
+impl JsonSerOptions {
+    /// Omit fields of objects whose value is `null`, at any depth. Nulls in arrays
+    /// are kept, since removing them would shift the elements.
+    pub fn skip_nulls(mut self, enabled: bool) -> Self {
+        self.skip_nulls = enabled;
+        self
+    }
+
+    /// Rename keys of objects to the naming convention, at any depth.
+    ///
+    /// When several keys of an object are renamed to the same key, e.g. `user_name` and
+    /// `userName` to camel case, only one of them is kept: the key which is already in the
+    /// naming convention, otherwise the first key by order of the object.
+    pub fn key_case(mut self, key_case: KeyCase) -> Self {
+        self.key_case = key_case;
+        self
+    }
+
+    /// Apply the options to a serialized json value.
+    fn apply(&self, value: Value) -> Value {
+        match value {
+            Value::Object(object) => {
+                let mut renamed = Map::new();
+                for (key, value) in object {
+                    if self.skip_nulls && value.is_null() {
+                        continue;
+                    }
+                    let name = self.rename(&key);
+                    // a key already in the convention wins over keys renamed to it
+                    if name == key || !renamed.contains_key(&name) {
+                        renamed.insert(name, self.apply(value));
+                    }
+                }
+                Value::Object(renamed)
+            }
+            Value::Array(values) => {
+                Value::Array(values.into_iter().map(|value| self.apply(value)).collect())
+            }
+            value => value,
+        }
+    }
+
+    fn rename(&self, key: &str) -> String {
+        match self.key_case {
+            KeyCase::Preserve => key.to_string(),
+            KeyCase::Camel => {
+                let mut renamed = String::with_capacity(key.len());
+                let mut upper = false;
+                for c in key.chars() {
+                    if (c == '_' || c == '-') && !renamed.is_empty() {
+                        upper = true;
+                    } else if upper {
+                        renamed.extend(c.to_uppercase());
+                        upper = false;
+                    } else {
+                        renamed.push(c);
+                    }
+                }
+                renamed
+            }
+            KeyCase::Snake => {
+                let mut renamed = String::with_capacity(key.len() + 4);
+                let chars: Vec<char> = key.chars().collect();
+                for (i, &c) in chars.iter().enumerate() {
+                    if c.is_uppercase() {
+                        // a word starts after a lowercase or a digit, or at the last
+                        // capital of an acronym followed by a lowercase
+                        let prev = i.checked_sub(1).map(|i| chars[i]);
+                        let next = chars.get(i + 1);
+                        let starts_word = match prev {
+                            Some(prev) if prev.is_uppercase() => {
+                                next.map_or(false, |next| next.is_lowercase())
+                            }
+                            Some(prev) => prev.is_lowercase() || prev.is_ascii_digit(),
+                            None => false,
+                        };
+                        if starts_word && !renamed.ends_with('_') {
+                            renamed.push('_');
+                        }
+                        renamed.extend(c.to_lowercase());
+                    } else if c == '-' {
+                        renamed.push('_');
+                    } else {
+                        renamed.push(c);
+                    }
+                }
+                renamed
+            }
+        }
+    }
+}
+
+impl Default for JsonSerOptions {
+    fn default() -> Self {
+        JsonSerOptions {
+            skip_nulls: false,
+            key_case: KeyCase::Preserve,
+        }
+    }
+}
+
+/// Json responder serializing the model with [`JsonSerOptions`], see [`Json::with_options`].
+pub struct JsonWithOptions<T> {
+    value: T,
+    options: JsonSerOptions,
+}
+
+impl<T> Json<T> {
+    /// Respond with the value serialized with the options, e.g. skipping null fields.
+    ///
+    /// The options are applied to the output of the model, so it is serialized and
+    /// rewritten once more, and keys of objects end up sorted by name. The response is
+    /// otherwise same as of [`Json`] responder.
+    ///
+    /// ```rust,ignore
+    /// async fn index() -> JsonWithOptions<User> {
+    ///     let options = JsonSerOptions::default().skip_nulls(true).key_case(KeyCase::Camel);
+    ///     Json::with_options(User { name: "axre".to_string(), nickname: None }, options)
+    /// }
+    /// ```
+    pub fn with_options(value: T, options: JsonSerOptions) -> JsonWithOptions<T> {
+        JsonWithOptions { value, options }
+    }
+}
+
+impl<T: Model, Err: ErrorRenderer> Responder<Err> for JsonWithOptions<T> {
+    type Future = std::future::Ready<HttpResponse>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let JsonWithOptions { value, options } = self;
+        std::future::ready(respond_json(req, || {
+            let json = value.json(false);
+            match serde_json::from_str::<Value>(&json) {
+                Ok(serialized) => options.apply(serialized).to_string(),
+                Err(_) => json,
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rename(key_case: KeyCase, key: &str) -> String {
+        JsonSerOptions::default().key_case(key_case).rename(key)
+    }
+
+    #[test]
+    fn test_camel_case() {
+        let cases = [
+            ("user_name", "userName"),
+            ("user-name", "userName"),
+            ("userName", "userName"),
+            ("name", "name"),
+            ("_private", "_private"),
+            ("http_server_2", "httpServer2"),
+        ];
+        for (key, renamed) in cases {
+            assert_eq!(rename(KeyCase::Camel, key), renamed, "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_snake_case() {
+        let cases = [
+            ("userName", "user_name"),
+            ("user-name", "user_name"),
+            ("user_name", "user_name"),
+            ("UserName", "user_name"),
+            ("HTTPServer", "http_server"),
+            ("userID", "user_id"),
+            ("getHTTPResponse", "get_http_response"),
+            ("user2Name", "user2_name"),
+            ("already_Snake", "already_snake"),
+        ];
+        for (key, renamed) in cases {
+            assert_eq!(rename(KeyCase::Snake, key), renamed, "{}", key);
+        }
+        assert_eq!(rename(KeyCase::Preserve, "userName"), "userName");
+    }
+
+    #[test]
+    fn test_skip_nulls() {
+        let options = JsonSerOptions::default().skip_nulls(true);
+        let value = json!({
+            "name": "axre",
+            "nickname": null,
+            "address": {"city": null, "zip": "123", "geo": {"lat": null}},
+            "tags": [null, "a", {"b": null}],
+        });
+        assert_eq!(
+            options.apply(value),
+            json!({
+                "name": "axre",
+                "address": {"zip": "123", "geo": {}},
+                "tags": [null, "a", {}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_nested_rename() {
+        let options = JsonSerOptions::default().key_case(KeyCase::Camel);
+        let value = json!({"user_name": "axre", "home_address": {"zip_code": "123"}, "tag_list": [{"tag_name": null}]});
+        assert_eq!(
+            options.apply(value),
+            json!({"userName": "axre", "homeAddress": {"zipCode": "123"}, "tagList": [{"tagName": null}]})
+        );
+    }
+
+    #[test]
+    fn test_colliding_keys() {
+        let options = JsonSerOptions::default().key_case(KeyCase::Camel);
+        // the key already in camel case wins, whichever comes first
+        let value = json!({"user_name": 1, "userName": 2});
+        assert_eq!(options.apply(value), json!({"userName": 2}));
+        let value = json!({"user-name": 1, "user_name": 2});
+        assert_eq!(options.apply(value), json!({"userName": 1}));
+    }
+}
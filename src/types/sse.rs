@@ -0,0 +1,92 @@
+use dade::Model;
+use ntex::http::header::CACHE_CONTROL;
+use ntex::http::StatusCode;
+use ntex::time::{interval, Interval, Millis};
+use ntex::util::{Bytes, Stream};
+use ntex::web::{ErrorRenderer, HttpRequest, HttpResponse, Responder};
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Responder streaming models as server-sent events.
+///
+/// Each model is serialized as the `data` field of an event. While the stream has
+/// no model ready, a keep-alive comment is sent every 15 seconds by default.
+///
+/// ```rust,ignore
+/// async fn updates() -> EventStream<impl Stream<Item = Update>> {
+///     EventStream::new(updates_stream())
+/// }
+/// ```
+pub struct EventStream<S> {
+    stream: S,
+    keep_alive: Option<Millis>,
+}
+
+impl<S> EventStream<S> {
+    /// Create responder for a stream of models.
+    pub fn new(stream: S) -> Self {
+        EventStream {
+            stream,
+            keep_alive: Some(Millis(15_000)),
+        }
+    }
+
+    /// Change interval of keep-alive comments, `None` disables them.
+    pub fn keep_alive(mut self, interval: Option<Millis>) -> Self {
+        self.keep_alive = interval;
+        self
+    }
+}
+
+impl<S, T, Err: ErrorRenderer> Responder<Err> for EventStream<S>
+where
+    S: Stream<Item = T> + 'static,
+    T: Model,
+{
+    type Future = std::future::Ready<HttpResponse>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let events = Events {
+            stream: Box::pin(self.stream),
+            keep_alive: self.keep_alive.map(interval),
+        };
+
+        std::future::ready(
+            HttpResponse::build(StatusCode::OK)
+                .content_type("text/event-stream")
+                .header(CACHE_CONTROL, "no-cache")
+                .streaming(events),
+        )
+    }
+}
+
+/// Stream of serialized events.
+struct Events<S> {
+    stream: Pin<Box<S>>,
+    keep_alive: Option<Interval>,
+}
+
+impl<S, T> Stream for Events<S>
+where
+    S: Stream<Item = T>,
+    T: Model,
+{
+    type Item = Result<Bytes, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(model)) => Poll::Ready(Some(Ok(Bytes::from(format!(
+                "data: {}\n\n",
+                model.json(false)
+            ))))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => match self.keep_alive {
+                Some(ref keep_alive) if keep_alive.poll_tick(cx).is_ready() => {
+                    Poll::Ready(Some(Ok(Bytes::from_static(b": keep-alive\n\n"))))
+                }
+                _ => Poll::Pending,
+            },
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Json extractor checking types of fields while the body arrives.
+///
+/// Same as [`Json`](crate::types::Json), but for a body of json object, the type of each
+/// top level field, e.g. a string for a number, is checked against the schema of the
+/// model as soon as the first byte of the value arrives, and a mismatch aborts reading
+/// with [`JsonPayloadError::FieldType`]. Once the whole body is read, it is validated by
+/// the model as usual.
+///
+/// This rejects large documents early, but the body is still buffered as a whole, since
+/// the model validates a complete document. So memory is bounded by the limit of
+/// [`JsonConfig`] only. Nested fields and constraints besides json types, e.g. ranges,
+/// are validated at the end only.
+pub struct StreamingJson<T>(pub T);
+
+impl<T> StreamingJson<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for StreamingJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for StreamingJson<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for StreamingJson<T>
+where
+    T: Model + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let mut validator = FieldTypes::of::<T>();
+        let fut = JsonBody::new(&req, payload, &config)
+            .limit(config.limit)
+            .inspect(Box::new(move |chunk| validator.feed(chunk)));
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) => config.parse(&req, body).await,
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(StreamingJson)
+        })
+    }
+}
+
+/// Json types allowed for a field, and whether it is required.
+struct Field {
+    types: Vec<String>,
+    required: bool,
+}
+
+/// Incremental checker of types of top level fields of a json object.
+///
+/// It only tracks strings and nesting of the body, syntax errors are left to the parser.
+struct FieldTypes {
+    fields: HashMap<String, Field>,
+    started: bool,
+    object: bool,
+    depth: usize,
+    in_string: bool,
+    escape: bool,
+    expect_key: bool,
+    key: Option<Vec<u8>>,
+    value_of: Option<String>,
+}
+
+impl FieldTypes {
+    /// Create checker from json schema of model.
+    fn of<T: Model>() -> Self {
+        let schema: serde_json::Value = serde_json::from_str(&T::schema()).unwrap_or_default();
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .map(|names| names.iter().filter_map(|name| name.as_str()).collect())
+            .unwrap_or_default();
+
+        let mut fields = HashMap::new();
+        if let Some(properties) = schema["properties"].as_object() {
+            for (name, property) in properties {
+                // fields without a plain type, e.g. references, are not checked
+                let types = match &property["type"] {
+                    serde_json::Value::String(t) => vec![t.clone()],
+                    serde_json::Value::Array(ts) => ts
+                        .iter()
+                        .filter_map(|t| t.as_str().map(String::from))
+                        .collect(),
+                    _ => continue,
+                };
+                let required = required.contains(&name.as_str());
+                fields.insert(name.clone(), Field { types, required });
+            }
+        }
+        FieldTypes::new(fields)
+    }
+
+    /// Create checker of the fields.
+    fn new(fields: HashMap<String, Field>) -> Self {
+        FieldTypes {
+            fields,
+            started: false,
+            object: false,
+            depth: 0,
+            in_string: false,
+            escape: false,
+            expect_key: false,
+            key: None,
+            value_of: None,
+        }
+    }
+
+    /// Feed next chunk of body.
+    fn feed(&mut self, chunk: &[u8]) -> Result<(), JsonPayloadError> {
+        for &b in chunk {
+            if self.in_string {
+                if self.escape {
+                    self.escape = false;
+                } else if b == b'\\' {
+                    self.escape = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                    // keys with escapes are kept as is, so they never match a field
+                    if let Some(key) = self.key.take() {
+                        self.value_of = Some(String::from_utf8_lossy(&key).into_owned());
+                    }
+                    continue;
+                }
+                if let Some(ref mut key) = self.key {
+                    key.push(b);
+                }
+                continue;
+            }
+            if b.is_ascii_whitespace() {
+                continue;
+            }
+
+            if !self.started {
+                self.started = true;
+                self.object = b == b'{';
+            }
+            if self.object && self.depth == 1 && b != b':' {
+                if let Some(field) = self.value_of.take() {
+                    self.check(&field, b)?;
+                }
+            }
+
+            match b {
+                b'"' => {
+                    self.in_string = true;
+                    if self.object && self.depth == 1 && self.expect_key {
+                        self.expect_key = false;
+                        self.key = Some(Vec::new());
+                    }
+                }
+                b'{' | b'[' => {
+                    self.depth += 1;
+                    self.expect_key = self.object && self.depth == 1;
+                }
+                b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+                b',' => self.expect_key = self.object && self.depth == 1,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the first byte of value of a field against types of the field.
+    fn check(&self, name: &str, first: u8) -> Result<(), JsonPayloadError> {
+        let field = match self.fields.get(name) {
+            Some(field) => field,
+            None => return Ok(()),
+        };
+        let found = match first {
+            b'"' => "string",
+            b'{' => "object",
+            b'[' => "array",
+            b't' | b'f' => "boolean",
+            b'n' => "null",
+            b'-' | b'0'..=b'9' => "number",
+            // not a json value, left to the parser
+            _ => return Ok(()),
+        };
+        let allowed = field
+            .types
+            .iter()
+            .any(|t| t == found || (t == "integer" && found == "number"))
+            || (found == "null" && !field.required);
+        if allowed {
+            Ok(())
+        } else {
+            Err(JsonPayloadError::FieldType {
+                field: name.to_string(),
+                expected: field.types.join(" or "),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checker of `name` string, `age` integer, `score` number and optional `nick` string.
+    fn checker() -> FieldTypes {
+        let fields = [
+            ("name", "string", true),
+            ("age", "integer", true),
+            ("score", "number", true),
+            ("nick", "string", false),
+        ];
+        FieldTypes::new(
+            fields
+                .into_iter()
+                .map(|(name, t, required)| {
+                    let types = vec![t.to_string()];
+                    (name.to_string(), Field { types, required })
+                })
+                .collect(),
+        )
+    }
+
+    /// Feed the body in chunks of `size` bytes, returning the field of a type error.
+    fn feed(body: &str, size: usize) -> Result<(), String> {
+        let mut checker = checker();
+        for chunk in body.as_bytes().chunks(size) {
+            match checker.feed(chunk) {
+                Err(JsonPayloadError::FieldType { field, .. }) => return Err(field),
+                Err(e) => panic!("unexpected error {}", e),
+                Ok(()) => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the body fed at once, and by every size of chunks up to the whole body.
+    fn check(body: &str) -> Result<(), String> {
+        let res = feed(body, body.len());
+        for size in 1..body.len() {
+            assert_eq!(
+                feed(body, size),
+                res,
+                "chunks of {} bytes of {}",
+                size,
+                body
+            );
+        }
+        res
+    }
+
+    #[test]
+    fn test_field_types() {
+        assert_eq!(check(r#"{"name":"axre","age":3,"score":-1.5}"#), Ok(()));
+        assert_eq!(check(r#"{"name":1}"#), Err("name".to_string()));
+        assert_eq!(
+            check(r#"{ "name" : "axre" , "age" : "3" }"#),
+            Err("age".to_string())
+        );
+        assert_eq!(check(r#"{"score":true}"#), Err("score".to_string()));
+        // unknown fields are not checked
+        assert_eq!(check(r#"{"other":{"age":"x"}}"#), Ok(()));
+    }
+
+    #[test]
+    fn test_split_value() {
+        let body = r#"{"name":"axre","age":"12345"}"#;
+        let split = body.find("\"12345").unwrap();
+        let mut checker = checker();
+        assert!(checker.feed(body[..split].as_bytes()).is_ok());
+        assert!(matches!(
+            checker.feed(body[split..].as_bytes()),
+            Err(JsonPayloadError::FieldType { ref field, .. }) if field == "age"
+        ));
+    }
+
+    #[test]
+    fn test_escaped_quotes() {
+        // quotes of a value do not start a key
+        assert_eq!(check(r#"{"name":"x\",\"age\":\"y","age":1}"#), Ok(()));
+        assert_eq!(
+            check(r#"{"name":"x\",\"age\":1","age":"y"}"#),
+            Err("age".to_string())
+        );
+        // a key with an escape is kept as is, so it is not a field
+        assert_eq!(check(r#"{"a\"ge":"y","name":"\\"}"#), Ok(()));
+    }
+
+    #[test]
+    fn test_nested_values() {
+        // only the top level fields are checked, at any depth of nested values
+        let body = r#"{"other":{"age":"x","name":[1,{"score":"y"}]},"age":[[{"name":2}]]}"#;
+        assert_eq!(check(body), Err("age".to_string()));
+        let body = r#"{"other":[[{"age":"x"}],{"a":{"b":{"name":1}}}],"name":"axre"}"#;
+        assert_eq!(check(body), Ok(()));
+    }
+
+    #[test]
+    fn test_not_an_object() {
+        assert_eq!(check(r#"[{"name":1},{"age":"x"}]"#), Ok(()));
+        assert_eq!(check(r#""name""#), Ok(()));
+        assert_eq!(check("12"), Ok(()));
+    }
+
+    #[test]
+    fn test_null() {
+        assert_eq!(check(r#"{"nick":null}"#), Ok(()));
+        assert_eq!(check(r#"{"name":null}"#), Err("name".to_string()));
+    }
+
+    #[test]
+    fn test_integer_and_number() {
+        // fractions of integers are left to the model, only the json type is checked
+        assert_eq!(check(r#"{"age":1.5,"score":2}"#), Ok(()));
+        assert_eq!(check(r#"{"age":-0}"#), Ok(()));
+        assert_eq!(check(r#"{"score":"2"}"#), Err("score".to_string()));
+        assert_eq!(check(r#"{"age":"1"}"#), Err("age".to_string()));
+    }
+}
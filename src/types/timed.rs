@@ -0,0 +1,60 @@
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Extractor measuring time of the inner extractor, e.g. `Timed<Json<User>>`.
+///
+/// `elapsed` covers buffering of the body and parsing it, from the start of extraction
+/// until the inner extractor completes. Time waiting for a slow client is included.
+pub struct Timed<E> {
+    /// Value of the inner extractor
+    pub inner: E,
+    /// Time taken by the inner extractor
+    pub elapsed: Duration,
+}
+
+impl<E> Timed<E> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E> ops::Deref for Timed<E> {
+    type Target = E;
+
+    fn deref(&self) -> &E {
+        &self.inner
+    }
+}
+
+impl<E> ops::DerefMut for Timed<E> {
+    fn deref_mut(&mut self) -> &mut E {
+        &mut self.inner
+    }
+}
+
+impl<E, Err: ErrorRenderer> FromRequest<Err> for Timed<E>
+where
+    E: FromRequest<Err> + 'static,
+{
+    type Error = E::Error;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let start = Instant::now();
+        let fut = E::from_request(req, payload);
+        Box::pin(async move {
+            let inner = fut.await?;
+            Ok(Timed {
+                inner,
+                elapsed: start.elapsed(),
+            })
+        })
+    }
+}
@@ -0,0 +1,113 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{parse_body, JsonBody, JsonConfig};
+use dade::Model;
+use ntex::http::Payload;
+use ntex::util::Bytes;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Json extractor only validating the body by the model, e.g. for webhook verification.
+///
+/// The body is checked and validated same as [`Json`](crate::types::Json), but the
+/// parsed value is dropped right after validation, so the handler only learns that
+/// the body is valid.
+pub struct ValidateOnly<T>(PhantomData<fn() -> T>);
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for ValidateOnly<T>
+where
+    T: Model + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) => config.parse::<T>(&req, body).await.map(drop),
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(|_| ValidateOnly(PhantomData))
+        })
+    }
+}
+
+/// Json extractor validating the body by the model and keeping the original bytes, e.g. for
+/// a validating proxy forwarding the body upstream without serializing it again.
+///
+/// The body is read and checked for content type and limit same as
+/// [`Json`](crate::types::Json), but only a failure to read it fails the extraction. The
+/// result of validation is kept in `validation`, so the handler decides whether to reject
+/// an invalid body or to forward it anyway. The exact bytes are validated, so options
+/// rewriting the parsed body, e.g. [`JsonConfig::coerce_numeric_strings`], do not apply.
+/// Options checking the body do, so with [`JsonConfig::reject_duplicate_keys`] and
+/// [`JsonConfig::reject_trailing_bytes`] a body which the upstream could read differently
+/// than the model, e.g. taking the first of duplicate keys, is invalid.
+/// The body is buffered once and the parsed value is dropped right after validation, so
+/// memory is bounded by the limit.
+///
+/// ```rust,ignore
+/// async fn proxy(body: ValidatedBytes<Order>) -> Result<HttpResponse, JsonPayloadError> {
+///     body.validation?;
+///     Ok(forward(body.bytes).await)
+/// }
+/// ```
+pub struct ValidatedBytes<T> {
+    /// Original bytes of the body, after decoding
+    pub bytes: Bytes,
+    /// Result of validating the body by the model
+    pub validation: Result<(), JsonPayloadError>,
+    _model: PhantomData<fn() -> T>,
+}
+
+impl<T> ValidatedBytes<T> {
+    /// Deconstruct to the original bytes
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for ValidatedBytes<T>
+where
+    T: Model + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let bytes = match fut.await {
+                Ok(body) => body.freeze(),
+                Err(e) => {
+                    let res = Err(e);
+                    config.complete(&req, &res).await;
+                    return res;
+                }
+            };
+            let validation = config
+                .check_body(&bytes)
+                .and_then(|_| {
+                    config.parse_within(|| parse_body::<T>(&bytes, config.capture_rejected))
+                })
+                .map(drop);
+            config.complete(&req, &validation).await;
+            Ok(ValidatedBytes {
+                bytes,
+                validation,
+                _model: PhantomData,
+            })
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use crate::errors::JsonPayloadError;
+use crate::types::json::{snippet, syntax_error, JsonBody, JsonConfig};
+use ntex::http::Payload;
+use ntex::web::{ErrorRenderer, FromRequest, HttpRequest};
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+
+type PinBox<T> = Pin<Box<T>>;
+
+/// Json extractor for any json value, without a model.
+///
+/// The body is checked for content type and limit same as [`Json`](crate::types::Json),
+/// but only json syntax is validated, so it fails with [`JsonPayloadError::Syntax`]
+/// and never with [`JsonPayloadError::Deserialize`]. An empty body fails with
+/// [`JsonPayloadError::EmptyBody`] rather than a syntax error, unless it is accepted as
+/// `null` by [`JsonConfig::allow_empty_body`].
+pub struct JsonValue(pub serde_json::Value);
+
+impl JsonValue {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> serde_json::Value {
+        self.0
+    }
+}
+
+impl ops::Deref for JsonValue {
+    type Target = serde_json::Value;
+
+    fn deref(&self) -> &serde_json::Value {
+        &self.0
+    }
+}
+
+impl ops::DerefMut for JsonValue {
+    fn deref_mut(&mut self) -> &mut serde_json::Value {
+        &mut self.0
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for JsonValue {
+    type Error = JsonPayloadError;
+    type Future = PinBox<dyn Future<Output = Result<Self, Self::Error>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = JsonConfig::from_req(req);
+
+        let req = req.clone();
+        let fut = JsonBody::new(&req, payload, &config).limit(config.limit);
+        Box::pin(async move {
+            let res = match fut.await {
+                Ok(body) if body.is_empty() && config.allow_empty_body == Some(true) => {
+                    Ok(serde_json::Value::Null)
+                }
+                Ok(body) => config.parse_within(|| parse_value(&body, config.capture_rejected)),
+                Err(e) => Err(e),
+            };
+            config.complete(&req, &res).await;
+            res.map(JsonValue)
+        })
+    }
+}
+
+/// Parse bytes with the default config.
+///
+/// No request is at hand, so none of [`JsonConfig`] options apply, e.g. duplicate keys
+/// are not checked and no part of the body is captured into a syntax error.
+impl TryFrom<&[u8]> for JsonValue {
+    type Error = JsonPayloadError;
+
+    fn try_from(body: &[u8]) -> Result<Self, Self::Error> {
+        parse_value(body, 0).map(JsonValue)
+    }
+}
+
+fn parse_value(body: &[u8], capture: usize) -> Result<serde_json::Value, JsonPayloadError> {
+    if body.is_empty() {
+        return Err(JsonPayloadError::EmptyBody);
+    }
+    serde_json::from_slice(body)
+        .map_err(|source| syntax_error(body, source, snippet(body, capture)))
+}
@@ -0,0 +1,33 @@
+//! Validation of json messages received outside of http bodies, e.g. WebSocket frames.
+//!
+//! ```rust,ignore
+//! use ntex::ws;
+//!
+//! match frame {
+//!     ws::Frame::Text(text) => match axre::ws::parse_bytes::<Message>(&text) {
+//!         Ok(message) => { ... }
+//!         Err(err) => ws::Message::Text(err.to_string().into()),
+//!     },
+//!     ...
+//! }
+//! ```
+use crate::errors::JsonPayloadError;
+use crate::types::json::parse_body;
+use dade::Model;
+
+/// Parse and validate text of a message by the model, same as the body of
+/// [`Json`](crate::types::Json) extractor.
+pub fn parse<T: Model>(text: &str) -> Result<T, JsonPayloadError> {
+    parse_bytes(text.as_bytes())
+}
+
+/// Parse and validate bytes of a message by the model, e.g. of a text frame before it
+/// is decoded as utf-8.
+///
+/// Empty messages fail with [`JsonPayloadError::EmptyBody`], same as empty bodies.
+pub fn parse_bytes<T: Model>(data: &[u8]) -> Result<T, JsonPayloadError> {
+    if data.is_empty() {
+        return Err(JsonPayloadError::EmptyBody);
+    }
+    parse_body(data, 0)
+}
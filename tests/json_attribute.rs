@@ -0,0 +1,80 @@
+#![cfg(feature = "macros")]
+use axre::types::json::JsonConfig;
+use axre::types::{Json, JsonPart};
+use dade_derive::model;
+use ntex::http::header::CONTENT_TYPE;
+use ntex::http::StatusCode;
+use ntex::util::Bytes;
+use ntex::web::test::{call_service, init_service, read_body, TestRequest};
+use ntex::web::{self, App};
+
+#[model]
+struct User {
+    name: String,
+}
+
+#[axre::json(limit = 32)]
+async fn index(payload: Json<User>) -> String {
+    format!("Welcome {}!", payload.name)
+}
+
+#[axre::json(json_part = "meta", limit = "1kb")]
+async fn upload(upload: JsonPart<User>) -> String {
+    format!("{} uploaded {} files", upload.name, upload.files.len())
+}
+
+#[ntex::test]
+async fn test_attribute_overrides_app_state() {
+    let srv = init_service(
+        App::new()
+            .app_state(JsonConfig::default().limit(16).json_errors(true))
+            .route("/", web::post().to(index)),
+    )
+    .await;
+
+    // over the limit of the app, within the limit of the attribute
+    let req = TestRequest::post()
+        .uri("/")
+        .header(CONTENT_TYPE, "application/json")
+        .set_payload(r#"{"name":"axre-user"}"#)
+        .to_request();
+    let res = call_service(&srv, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // over the limit of the attribute, rendered as json by the config of the app
+    let req = TestRequest::post()
+        .uri("/")
+        .header(CONTENT_TYPE, "application/json")
+        .set_payload(r#"{"name":"a name longer than the limit"}"#)
+        .to_request();
+    let res = call_service(&srv, req).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(
+        res.headers().get(CONTENT_TYPE).unwrap(),
+        "application/json; charset=utf-8"
+    );
+}
+
+#[ntex::test]
+async fn test_attribute_string_option() {
+    let srv = init_service(App::new().route("/", web::post().to(upload))).await;
+
+    let body = "--xyz\r\n\
+        Content-Disposition: form-data; name=\"meta\"\r\n\r\n\
+        {\"name\":\"axre\"}\r\n\
+        --xyz\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\r\n\
+        hello\r\n\
+        --xyz--\r\n";
+    let req = TestRequest::post()
+        .uri("/")
+        .header(CONTENT_TYPE, "multipart/form-data; boundary=xyz")
+        .set_payload(body)
+        .to_request();
+    let res = call_service(&srv, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        read_body(res).await,
+        Bytes::from_static(b"axre uploaded 1 files")
+    );
+}